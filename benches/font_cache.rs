@@ -0,0 +1,216 @@
+//! Times a full run of the layout and parsing stages over a PDF with the font cache and with the
+//! fonts mapped again for every page, and reports the measured difference.
+//!
+//! The dictionary isn't distributed with the source, so by default the benchmark runs over a
+//! synthetic PDF: the fixture entries laid out as in the dictionary, drawn with two Type0 fonts
+//! whose ToUnicode CMaps are as large as those of a CJK font.  Point it at a copy of the dictionary
+//! to time a real run:
+//!
+//! ```text
+//! DICTIONARY_PDF=path/to/dictionary.pdf cargo bench --bench font_cache
+//! ```
+
+use std::{
+    fmt::Write,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use danish_dictionary_parser::{
+    decode_pdf_string::{DecodeStats, FontCache},
+    layout::{decode_lines, LayoutConfig, Words},
+    parse_dictionary::parse_entries,
+};
+use itertools::Itertools;
+
+/// Number of timed runs of each variant, after a warm-up run.
+const RUNS: usize = 5;
+/// Number of pages of the synthetic PDF, about the length of the dictionary.
+const SYNTHETIC_PAGES: usize = 600;
+/// Number of codes mapped by each synthetic font, about as many as a subset CJK font maps.
+const SYNTHETIC_CODES: usize = 8000;
+
+fn main() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path: PathBuf = match std::env::var_os("DICTIONARY_PDF") {
+        Some(path) => path.into(),
+        None => {
+            let path = dir.path().join("synthetic.pdf");
+            fs_err::write(&path, synthetic_pdf(SYNTHETIC_PAGES))?;
+            println!("timing a synthetic PDF of {SYNTHETIC_PAGES} pages");
+            path
+        }
+    };
+    let file = pdf::file::File::open(&path)?;
+
+    let cached = median_run(&file, FontCache::default)?;
+    let rebuilding = median_run(&file, FontCache::rebuilding)?;
+    println!("with the font cache:    {cached:.3?}");
+    println!("without the font cache: {rebuilding:.3?}");
+    match rebuilding.checked_sub(cached) {
+        Some(saved) => println!(
+            "the cache saves {saved:.3?} ({:.1}%)",
+            100. * saved.as_secs_f64() / rebuilding.as_secs_f64()
+        ),
+        None => println!("the cache costs {:.3?}", cached - rebuilding),
+    }
+    Ok(())
+}
+
+fn median_run(
+    file: &pdf::file::File<Vec<u8>>,
+    cache: impl Fn() -> FontCache,
+) -> anyhow::Result<Duration> {
+    run(file, cache())?;
+    let times: Vec<Duration> = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run(file, cache())?;
+            Ok(start.elapsed())
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok(times.into_iter().sorted().nth(RUNS / 2).unwrap())
+}
+
+/// Extracts and parses every page, as the parse subcommand does.
+fn run(file: &pdf::file::File<Vec<u8>>, mut fonts: FontCache) -> anyhow::Result<()> {
+    let config = LayoutConfig::default();
+    let mut stats = DecodeStats::default();
+    let lines = (0..file.num_pages()).map(|number| {
+        let page = file.get_page(number)?;
+        let lines = config.group_lines(file, &page)?;
+        let page_fonts = fonts.page_fonts(file, &page)?;
+        config.lines(decode_lines(&page_fonts, lines, &mut stats)?)
+    });
+    let words = Words::new(lines.flatten_ok());
+    // Entries that fail to parse cost the same with and without the cache
+    parse_entries(words, &[])?.for_each(drop);
+    Ok(())
+}
+
+/// A PDF of `pages` pages of 60 lines each, repeating the fixture entries.  The headword of each
+/// entry is drawn with one font and the rest of the line with another, like the dictionary does.
+fn synthetic_pdf(pages: usize) -> Vec<u8> {
+    let texts = include_str!("../tests/fixtures/entries.txt")
+        .lines()
+        .collect_vec();
+    // The codes of the characters of the fixture, then filler codes for a full CJK font
+    let chars = texts
+        .iter()
+        .flat_map(|text| text.chars())
+        .unique()
+        .collect_vec();
+    let mut to_unicode = (1..).zip(chars.iter().copied()).collect_vec();
+    let filler = ('\u{4E00}'..).take(SYNTHETIC_CODES.saturating_sub(chars.len()));
+    to_unicode.extend((0x1000..).zip(filler));
+    let code = |c: char| chars.iter().position(|&d| d == c).expect("Mapped above") + 1;
+    let hex = |text: &str| text.chars().map(|c| format!("{:04X}", code(c))).join("");
+
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+        String::new(), // The page tree, once the pages are known
+    ];
+    let mut fonts = vec![];
+    for name in ["Synthetic-Mincho", "Synthetic-Gothic"] {
+        let font = objects.len() + 1;
+        fonts.push(font);
+        objects.push(format!(
+            "<< /Type /Font /Subtype /Type0 /BaseFont /{name} /Encoding /Identity-H \
+             /DescendantFonts [{} 0 R] /ToUnicode {} 0 R >>",
+            font + 1,
+            font + 3
+        ));
+        objects.push(format!(
+            "<< /Type /Font /Subtype /CIDFontType2 /BaseFont /{name} \
+             /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> \
+             /FontDescriptor {} 0 R /DW 1000 >>",
+            font + 2
+        ));
+        objects.push(format!(
+            "<< /Type /FontDescriptor /FontName /{name} /Flags 4 /FontBBox [0 -120 1000 880] \
+             /ItalicAngle 0 /Ascent 880 /Descent -120 /CapHeight 700 /StemV 80 >>"
+        ));
+        objects.push(stream(&cmap(&to_unicode)));
+    }
+    let resources = format!(
+        "<< /Font << /F1 {} 0 R /F2 {} 0 R >> >>",
+        fonts[0], fonts[1]
+    );
+
+    let mut lines = texts.iter().cycle();
+    let mut kids = vec![];
+    for _ in 0..pages {
+        let mut content = String::new();
+        for y in (0..60).map(|i| 800 - 12 * i) {
+            let text = lines.next().expect("Cycled");
+            let (headword, rest) = text.split_at(text.find(' ').unwrap_or(text.len()));
+            writeln!(
+                content,
+                "BT /F2 1 Tf 9 0 0 9 71 {y} Tm <{}> Tj /F1 1 Tf <{}> Tj ET",
+                hex(headword),
+                hex(rest)
+            )
+            .expect("Writing to a String");
+        }
+        let page = objects.len() + 1;
+        kids.push(format!("{page} 0 R"));
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] /Resources {resources} \
+             /Contents {} 0 R >>",
+            page + 1
+        ));
+        objects.push(stream(&content));
+    }
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {pages} >>",
+        kids.join(" ")
+    );
+
+    let mut pdf = b"%PDF-1.7\n".to_vec();
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{object}\nendobj\n", i + 1).as_bytes());
+    }
+    let xref = pdf.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        writeln!(trailer, "{offset:010} 00000 n ").expect("Writing to a String");
+    }
+    write!(
+        trailer,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    )
+    .expect("Writing to a String");
+    pdf.extend_from_slice(trailer.as_bytes());
+    pdf
+}
+
+fn stream(data: &str) -> String {
+    format!("<< /Length {} >>\nstream\n{data}\nendstream", data.len())
+}
+
+/// A ToUnicode CMap with two-byte codes.
+fn cmap(to_unicode: &[(u32, char)]) -> String {
+    let mut cmap = "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+        /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+        /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+        1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n"
+        .to_owned();
+    // A bfchar block has at most 100 mappings
+    for chunk in to_unicode.chunks(100) {
+        writeln!(cmap, "{} beginbfchar", chunk.len()).expect("Writing to a String");
+        for &(code, c) in chunk {
+            let utf16 = c
+                .encode_utf16(&mut [0; 2])
+                .iter()
+                .map(|u| format!("{u:04X}"))
+                .join("");
+            writeln!(cmap, "<{code:04X}> <{utf16}>").expect("Writing to a String");
+        }
+        cmap += "endbfchar\n";
+    }
+    cmap += "endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend";
+    cmap
+}
@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// The corrected text of an entry, as authored in the `review` subcommand.  Unlike [`patch`],
+/// corrections are read at run time, so fixing an entry doesn't need a rebuild.
+///
+/// [`patch`]: crate::parse_dictionary::patch
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Correction {
+    /// The text of the entry as extracted from the PDF.
+    pub original: String,
+    pub corrected: String,
+}
+
+/// Reads corrections written by [`write_corrections`], one JSON object per line.  A missing file
+/// has no corrections.
+pub fn read_corrections(path: &Path) -> anyhow::Result<Vec<Correction>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    fs_err::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+pub fn write_corrections(path: &Path, corrections: &[Correction]) -> anyhow::Result<()> {
+    let mut text = String::new();
+    for correction in corrections {
+        text += &serde_json::to_string(correction)?;
+        text.push('\n');
+    }
+    fs_err::write(path, text)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        layout::Word,
+        parse_dictionary::{parse_entries, patch},
+    };
+
+    #[test]
+    fn corrections_round_trip_through_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("corrections.jsonl");
+        assert_eq!(read_corrections(&path).unwrap(), []);
+
+        let corrections = [
+            Correction {
+                original: "knuse [動] [ˈknu:sə] 壊す． ".to_owned(),
+                corrected: "knuse [動] [ˈknu:sə]: 壊す． ".to_owned(),
+            },
+            Correction {
+                original: "lille \"[形]\"\n".to_owned(),
+                corrected: "lille [形]: ".to_owned(),
+            },
+        ];
+        write_corrections(&path, &corrections).unwrap();
+        assert_eq!(read_corrections(&path).unwrap(), corrections);
+    }
+
+    #[test]
+    fn corrections_take_precedence_over_patches() {
+        let original =
+            "Amager [固] [ˈαˌmα;] アマー［地名：コペンハーゲン南部の島．Kastrup空港がある］． ";
+        assert_ne!(patch(original), original);
+        let corrections = [Correction {
+            original: original.to_owned(),
+            corrected: "Amager [固] [ˈαˌmα;]: アマー． ".to_owned(),
+        }];
+        let words = [Ok(Word {
+            text: original.to_owned(),
+            section: None,
+        })];
+        let entries = parse_entries(words, &corrections)
+            .unwrap()
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].word, "Amager");
+        assert_eq!(entries[0].definition, "アマー．");
+    }
+}
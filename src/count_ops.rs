@@ -1,25 +1,190 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use anyhow::Context;
 use itertools::Itertools;
+use ordered_float::OrderedFloat;
 use pdf::content::Op;
+use serde::{Serialize, Serializer};
 
-pub fn count_ops(file: &pdf::file::File<Vec<u8>>) -> Result<(), anyhow::Error> {
-    let mut count = HashMap::<_, u64>::new();
-    for page in file.pages().flatten() {
-        let contents = page
-            .contents
-            .as_ref()
-            .context("The page does not have contents")?;
+use crate::{
+    decode_pdf_string::{Codespace, FontCache},
+    walk_text::each_text,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum OpsFormat {
+    Table,
+    Json,
+}
+
+/// Statistics of the content streams of a document, per page and in total.
+#[derive(Default, Serialize)]
+pub struct OpsReport {
+    pub pages: Vec<PageOps>,
+    pub total: OpStats,
+}
+
+#[derive(Serialize)]
+pub struct PageOps {
+    pub page: u32,
+    /// Pages without contents have no statistics.
+    pub has_contents: bool,
+    #[serde(flatten)]
+    pub stats: OpStats,
+}
+
+#[derive(Default, Serialize)]
+pub struct OpStats {
+    /// Number of each content stream operator.
+    pub ops: BTreeMap<&'static str, u64>,
+    /// Number of each marked content tag, e.g. `Artifact` for headers and footers.
+    pub marked_content: BTreeMap<String, u64>,
+    /// Usage of each font by its resource name.
+    pub fonts: BTreeMap<String, FontOps>,
+}
+
+#[derive(Default, Serialize)]
+pub struct FontOps {
+    /// Number of strings drawn, which are the text entries of the layout stage.
+    pub strings: u64,
+    /// Number of codes drawn.  Without a map for the font, the number of bytes.
+    pub glyphs: u64,
+    /// Number of strings drawn at each glyph size, as compared with `LayoutConfig::heading_size`.
+    #[serde(serialize_with = "serialize_float_counts")]
+    pub sizes: BTreeMap<OrderedFloat<f32>, u64>,
+    /// Number of strings starting at each x coordinate, rounded to a tenth of a point, as
+    /// compared with `LayoutConfig::entry_x` and `continuation_x`.
+    #[serde(serialize_with = "serialize_float_counts")]
+    pub x_starts: BTreeMap<OrderedFloat<f32>, u64>,
+}
+
+impl OpStats {
+    fn merge(&mut self, other: &Self) {
+        for (&op, count) in &other.ops {
+            *self.ops.entry(op).or_default() += count;
+        }
+        for (tag, count) in &other.marked_content {
+            *self.marked_content.entry(tag.clone()).or_default() += count;
+        }
+        for (font, other) in &other.fonts {
+            let font = self.fonts.entry(font.clone()).or_default();
+            font.strings += other.strings;
+            font.glyphs += other.glyphs;
+            for (size, count) in &other.sizes {
+                *font.sizes.entry(*size).or_default() += count;
+            }
+            for (x, count) in &other.x_starts {
+                *font.x_starts.entry(*x).or_default() += count;
+            }
+        }
+    }
+}
+
+/// Collects the statistics of `pages`.  Pages without contents are reported as such instead of
+/// failing the run.
+pub fn count_ops(file: &pdf::file::File<Vec<u8>>, pages: &[u32]) -> anyhow::Result<OpsReport> {
+    let mut report = OpsReport::default();
+    let mut fonts = FontCache::default();
+    for &number in pages {
+        let page = file.get_page(number)?;
+        let mut stats = OpStats::default();
+        let contents = match &page.contents {
+            Some(contents) => contents,
+            None => {
+                report.pages.push(PageOps {
+                    page: number,
+                    has_contents: false,
+                    stats,
+                });
+                continue;
+            }
+        };
         for op in contents.operations(file)? {
-            let op = op_name_to_string(op);
-            *count.entry(op).or_default() += 1;
+            match &op {
+                Op::BeginMarkedContent { tag, .. } | Op::MarkedContentPoint { tag, .. } => {
+                    *stats.marked_content.entry(tag.as_str().into()).or_default() += 1;
+                }
+                _ => {}
+            }
+            *stats.ops.entry(op_name_to_string(op)).or_default() += 1;
+        }
+
+        // The codespace of a font is only known once its map is made, which fails for fonts
+        // that can't be decoded.  Only the codes of those fonts are counted as bytes.
+        let resources = page.resources()?;
+        let codespaces: HashMap<&str, Codespace> = (resources.fonts())
+            .filter_map(|(name, &font)| {
+                let (_, map) = fonts.font(file, font).ok()?;
+                Some((name, map.codespace.clone()))
+            })
+            .collect();
+        for entry in each_text(file, &page)? {
+            let entry = entry?;
+            let glyphs = match codespaces.get(entry.font.as_str()) {
+                Some(codespace) => codespace.codes(entry.text.as_bytes()).count(),
+                None => entry.text.as_bytes().len(),
+            };
+            let x = entry.positions.coordinates().x;
+            let font = stats.fonts.entry(entry.font.as_str().into()).or_default();
+            font.strings += 1;
+            font.glyphs += glyphs as u64;
+            let size = OrderedFloat(entry.positions.glyph_size());
+            *font.sizes.entry(size).or_default() += 1;
+            let x = OrderedFloat((x * 10.).round() / 10.);
+            *font.x_starts.entry(x).or_default() += 1;
+        }
+
+        report.total.merge(&stats);
+        report.pages.push(PageOps {
+            page: number,
+            has_contents: true,
+            stats,
+        });
+    }
+    Ok(report)
+}
+
+fn serialize_float_counts<S: Serializer>(
+    counts: &BTreeMap<OrderedFloat<f32>, u64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(counts.iter().map(|(k, v)| (k.to_string(), v)))
+}
+
+pub fn print_ops_table(report: &OpsReport) {
+    for page in &report.pages {
+        println!("Page {}", page.page);
+        if page.has_contents {
+            print_stats(&page.stats);
+        } else {
+            println!("  (no contents)");
         }
     }
-    for (k, v) in count.iter().sorted_by_key(|x| x.1).rev() {
-        println!("{k:25}\t{v}");
+    println!("Total");
+    print_stats(&report.total);
+}
+
+fn print_stats(stats: &OpStats) {
+    for (op, count) in stats.ops.iter().sorted_by_key(|x| x.1).rev() {
+        println!("  {op:25}\t{count}");
+    }
+    for (tag, count) in &stats.marked_content {
+        println!("  marked content {tag:10}\t{count}");
+    }
+    for (name, font) in &stats.fonts {
+        println!(
+            "  font {name}: {} strings, {} glyphs",
+            font.strings, font.glyphs
+        );
+        println!("    sizes:    {}", format_counts(&font.sizes));
+        println!("    x starts: {}", format_counts(&font.x_starts));
     }
-    Ok(())
+}
+
+fn format_counts(counts: &BTreeMap<OrderedFloat<f32>, u64>) -> String {
+    counts
+        .iter()
+        .map(|(value, count)| format!("{value}×{count}"))
+        .join(" ")
 }
 
 fn op_name_to_string(op: Op) -> &'static str {
@@ -71,3 +236,46 @@ fn op_name_to_string(op: Op) -> &'static str {
         Op::InlineImage { .. } => "InlineImage",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font_ops(strings: u64, glyphs: u64, size: f32, x: f32) -> FontOps {
+        FontOps {
+            strings,
+            glyphs,
+            sizes: BTreeMap::from([(OrderedFloat(size), strings)]),
+            x_starts: BTreeMap::from([(OrderedFloat(x), strings)]),
+        }
+    }
+
+    #[test]
+    fn merging_adds_up_every_count() {
+        let mut total = OpStats {
+            ops: BTreeMap::from([("Tj", 3), ("BT", 1)]),
+            marked_content: BTreeMap::from([("Artifact".to_owned(), 1)]),
+            fonts: BTreeMap::from([("F1".to_owned(), font_ops(3, 12, 9.0, 71.0))]),
+        };
+        let page = OpStats {
+            ops: BTreeMap::from([("Tj", 2), ("TJ", 1)]),
+            marked_content: BTreeMap::from([("Artifact".to_owned(), 2)]),
+            fonts: BTreeMap::from([
+                ("F1".to_owned(), font_ops(2, 5, 9.0, 81.0)),
+                ("F2".to_owned(), font_ops(1, 4, 12.0, 71.0)),
+            ]),
+        };
+        total.merge(&page);
+
+        assert_eq!(total.ops, BTreeMap::from([("BT", 1), ("TJ", 1), ("Tj", 5)]));
+        assert_eq!(total.marked_content["Artifact"], 3);
+        let f1 = &total.fonts["F1"];
+        assert_eq!((f1.strings, f1.glyphs), (5, 17));
+        assert_eq!(f1.sizes, BTreeMap::from([(OrderedFloat(9.0), 5)]));
+        assert_eq!(
+            f1.x_starts,
+            BTreeMap::from([(OrderedFloat(71.0), 3), (OrderedFloat(81.0), 2)])
+        );
+        assert_eq!(total.fonts["F2"].glyphs, 4);
+    }
+}
@@ -0,0 +1,141 @@
+use std::{collections::HashMap, fmt::Write};
+
+use itertools::Itertools;
+use pdf::object::PageRc;
+
+use crate::{
+    decode_pdf_string::{DecodeStats, FontCache},
+    layout::{decode_lines, LayoutConfig, LineKind, ParsedTextEntry},
+    output::escape_html,
+};
+
+const FONT_COLORS: [&str; 8] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+/// Draws the layout of `page` as the layout stage sees it, as an SVG document:
+///
+/// - the bands of `entry_x` (green) and `continuation_x` (blue) across the page
+/// - the footer that is skipped, below `footer_y`, in grey
+/// - a box for each text entry, coloured by font, with its text and coordinates on hover
+/// - a frame around each line, coloured by how it is classified: green for entry starts, blue
+///   for continuations, purple for headings, grey for empty lines and red where classification
+///   fails, with the reason on hover
+///
+/// The PDF doesn't give glyph widths to the layout stage, so the boxes are as wide as the glyphs
+/// would be at half their size for single-byte text and at their size for CJK text.
+pub fn debug_render(
+    config: &LayoutConfig,
+    file: &pdf::file::File<Vec<u8>>,
+    fonts: &mut FontCache,
+    page: &PageRc,
+    stats: &mut DecodeStats,
+) -> anyhow::Result<String> {
+    let media_box = page.media_box()?;
+    let (left, top) = (media_box.left, media_box.top);
+    let width = media_box.right - media_box.left;
+    let height = media_box.top - media_box.bottom;
+    // PDF y grows upwards, SVG y downwards
+    let to_svg = |x: f32, y: f32| (x - left, top - y);
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {height}" width="{width}" height="{height}" font-family="sans-serif">"#
+    )?;
+    writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+
+    let bands = std::iter::once((&config.entry_x, "#2ca02c", "entry_x"))
+        .chain((config.continuation_x.iter()).map(|band| (band, "#1f77b4", "continuation_x")));
+    for (band, color, name) in bands {
+        let (x, _) = to_svg(band.start, 0.);
+        writeln!(
+            svg,
+            r#"<rect x="{x}" y="0" width="{}" height="{height}" fill="{color}" fill-opacity="0.15"><title>{name} {:?}</title></rect>"#,
+            (band.end - band.start).max(0.5),
+            band
+        )?;
+    }
+    let (_, footer) = to_svg(0., config.footer_y);
+    writeln!(
+        svg,
+        r##"<rect x="0" y="{footer}" width="{width}" height="{}" fill="#999" fill-opacity="0.2"><title>Skipped footer, footer_y = {}</title></rect>"##,
+        (height - footer).max(0.),
+        config.footer_y
+    )?;
+
+    let page_fonts = fonts.page_fonts(file, page)?;
+    let font_colors: HashMap<&str, &str> = (page_fonts.keys().sorted())
+        .zip(FONT_COLORS.iter().cycle())
+        .map(|(&name, &color)| (name, color))
+        .collect();
+
+    let lines = decode_lines(&page_fonts, config.group_lines(file, page)?, stats)?;
+    for line in &lines {
+        let boxes = line
+            .iter()
+            .map(|entry| text_box(entry, &to_svg))
+            .collect_vec();
+        for ((entry, text), &(x, y, w, h)) in line.iter().zip(&boxes) {
+            let color = font_colors.get(entry.font.as_str()).unwrap_or(&"black");
+            let p = entry.positions.coordinates();
+            let text = escape_html(&text.concat());
+            writeln!(
+                svg,
+                r#"<g><title>{text} — {} at ({:.2}, {:.2}), size {:.2}</title><rect x="{x}" y="{y}" width="{w}" height="{h}" fill="{color}" fill-opacity="0.25" stroke="{color}" stroke-width="0.3"/><text x="{x}" y="{}" font-size="{}" fill="black">{text}</text></g>"#,
+                entry.font.as_str(),
+                p.x,
+                p.y,
+                entry.positions.glyph_size(),
+                y + h * 0.85,
+                h * 0.8,
+            )?;
+        }
+
+        let (color, label) = match config.classify(line) {
+            Ok(LineKind::EntryStart) => ("#2ca02c", "entry start".to_owned()),
+            Ok(LineKind::Continuation) => ("#1f77b4", "continuation".to_owned()),
+            Ok(LineKind::Heading) => ("#9467bd", "heading".to_owned()),
+            Ok(LineKind::Empty) => ("#999", "empty".to_owned()),
+            Err(e) => ("#d62728", format!("{e:#}")),
+        };
+        let (mut x0, mut y0) = (f32::INFINITY, f32::INFINITY);
+        let (mut x1, mut y1) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for &(x, y, w, h) in &boxes {
+            x0 = x0.min(x - 1.);
+            y0 = y0.min(y - 1.);
+            x1 = x1.max(x + w + 1.);
+            y1 = y1.max(y + h + 1.);
+        }
+        writeln!(
+            svg,
+            r#"<rect x="{x0}" y="{y0}" width="{}" height="{}" fill="none" stroke="{color}" stroke-width="0.6"><title>{}</title></rect>"#,
+            x1 - x0,
+            y1 - y0,
+            escape_html(&label)
+        )?;
+    }
+    writeln!(svg, "</svg>")?;
+    Ok(svg)
+}
+
+/// The box of a text entry in SVG coordinates: x, y, width and height.
+fn text_box(
+    (entry, text): &ParsedTextEntry,
+    to_svg: &impl Fn(f32, f32) -> (f32, f32),
+) -> (f32, f32, f32, f32) {
+    let p = entry.positions.coordinates();
+    let size = entry.positions.glyph_size().abs().max(1.);
+    let width = (text.iter().flat_map(|s| s.chars()))
+        .map(|c| {
+            if c.is_ascii() || c.len_utf8() < 3 {
+                0.5
+            } else {
+                1.
+            }
+        })
+        .sum::<f32>()
+        * size;
+    let (x, y) = to_svg(p.x, p.y);
+    (x, y - size, width.max(0.5), size)
+}
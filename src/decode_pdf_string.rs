@@ -1,70 +1,491 @@
-use std::collections::HashMap;
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use anyhow::bail;
+use anyhow::{bail, Context};
 use itertools::Itertools;
 use pdf::{
     encoding::{BaseEncoding, Encoding},
     font::{Font, FontType},
-    object::{PageRc, RcRef, Resolve},
-    primitive::PdfString,
+    object::{PageRc, PlainRef, RcRef, Ref, Resolve, Stream},
+    primitive::{PdfString, Primitive},
 };
+use regex::Regex;
 
-pub type FontMap = HashMap<u16, String>;
-pub fn make_font_map<'p>(
-    file: &pdf::file::File<Vec<u8>>,
-    page: &'p PageRc,
-) -> anyhow::Result<HashMap<&'p str, (RcRef<Font>, FontMap)>> {
-    page.resources()?
-        .fonts()
-        .map(|(k, &font)| {
-            let font = file.get(font)?;
-            let map = make_unicode_map(file, &font)?;
-            anyhow::Ok((k, (font, map)))
+pub struct FontMap {
+    /// The font the map was made from.
+    pub font: PlainRef,
+    pub name: String,
+    pub codespace: Codespace,
+    pub map: HashMap<u32, String>,
+}
+
+pub type PageFonts<'p, 'c> = HashMap<&'p str, &'c (RcRef<Font>, FontMap)>;
+
+/// Fonts of a document, resolved and mapped once and then shared by every page that uses them.
+#[derive(Default)]
+pub struct FontCache {
+    fonts: HashMap<PlainRef, (RcRef<Font>, FontMap)>,
+    /// Whether the fonts are resolved and mapped again for every page, as without the cache.
+    rebuild: bool,
+    pub counters: FontCacheCounters,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FontCacheCounters {
+    pub hits: u64,
+    pub misses: u64,
+    pub build_time: Duration,
+}
+
+impl FontCache {
+    /// A cache that keeps nothing between pages, to measure what the cache saves.
+    pub fn rebuilding() -> Self {
+        Self {
+            rebuild: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn page_fonts<'p, 'c>(
+        &'c mut self,
+        file: &pdf::file::File<Vec<u8>>,
+        page: &'p PageRc,
+    ) -> anyhow::Result<PageFonts<'p, 'c>> {
+        let resources = page.resources()?;
+        if self.rebuild {
+            self.fonts.clear();
+        }
+        for (_, &font) in resources.fonts() {
+            self.font(file, font)?;
+        }
+        let fonts = &self.fonts;
+        Ok(resources
+            .fonts()
+            .map(|(k, font)| (k, &fonts[&font.get_inner()]))
+            .collect())
+    }
+
+    /// Resolves and maps a single font, so that a font that can't be mapped doesn't keep the
+    /// other fonts of its page from being used.
+    pub fn font(
+        &mut self,
+        file: &pdf::file::File<Vec<u8>>,
+        font: Ref<Font>,
+    ) -> anyhow::Result<&(RcRef<Font>, FontMap)> {
+        Ok(match self.fonts.entry(font.get_inner()) {
+            Entry::Occupied(e) => {
+                self.counters.hits += 1;
+                e.into_mut()
+            }
+            Entry::Vacant(e) => {
+                self.counters.misses += 1;
+                let start = Instant::now();
+                let font = file.get(font)?;
+                let map = make_unicode_map(file, &font)?;
+                self.counters.build_time += start.elapsed();
+                e.insert((font, map))
+            }
         })
-        .try_collect()
+    }
+}
+
+impl FontCacheCounters {
+    pub fn merge(&mut self, other: Self) {
+        self.hits += other.hits;
+        self.misses += other.misses;
+        self.build_time += other.build_time;
+    }
+
+    /// Prints what was measured.  What the cache saves on a whole run is measured by the
+    /// `font_cache` benchmark.
+    pub fn print_summary(&self) {
+        eprintln!(
+            "Font cache: built {} font maps in {:.3?}, reused them {} times",
+            self.misses, self.build_time, self.hits,
+        );
+    }
+}
+
+pub const REPLACEMENT_CHARACTER: &str = "\u{FFFD}";
+
+#[derive(Debug, thiserror::Error)]
+#[error("Code {code:#x} is not mapped in font {font:?}")]
+pub struct UnmappedCode {
+    pub font: String,
+    pub code: u32,
 }
 
 pub fn decode_pdf_string<'b, 'a: 'b>(
     map: &'a FontMap,
-    subtype: FontType,
     text: &'b PdfString,
-) -> anyhow::Result<impl Iterator<Item = &'a String> + 'b> {
-    Ok(match subtype {
-        FontType::TrueType => {
-            itertools::Either::Left(text.as_bytes().iter().map(|&b| &map[&(b as u16)]))
-        }
-        FontType::Type0 => itertools::Either::Right(
-            text.as_bytes()
-                .chunks(2)
-                .map(|c| &map[&u16::from_be_bytes(c.try_into().unwrap())]),
-        ),
-        _ => bail!("Unsupported font type {subtype:?}"),
+) -> impl Iterator<Item = Result<&'a str, UnmappedCode>> + 'b {
+    map.codespace.codes(text.as_bytes()).map(|code| {
+        map.map
+            .get(&code)
+            .map(|s| s.as_str())
+            .ok_or_else(|| UnmappedCode {
+                font: map.name.clone(),
+                code,
+            })
+    })
+}
+
+/// Decodes `text`, replacing unmapped codes with U+FFFD.  Every code is recorded in `stats`, and
+/// the first occurrence of an unmapped code in each font is reported on stderr.
+pub fn decode_pdf_string_lossy<'a>(
+    map: &'a FontMap,
+    text: &PdfString,
+    stats: &mut DecodeStats,
+) -> Vec<&'a str> {
+    let stats = stats.font(map);
+    decode_pdf_string(map, text)
+        .map(|res| {
+            stats.decoded += 1;
+            res.unwrap_or_else(|e| {
+                let count = stats.unmapped.entry(e.code).or_default();
+                if *count == 0 {
+                    eprintln!("Warning: {e}");
+                }
+                *count += 1;
+                REPLACEMENT_CHARACTER
+            })
+        })
+        .collect()
+}
+
+/// Number of decoded and unmapped codes per font.  Fonts are told apart by their object rather
+/// than their name, as Type3 fonts may have no name and different fonts may share a `BaseFont`.
+#[derive(Default)]
+pub struct DecodeStats {
+    pub fonts: HashMap<PlainRef, FontStats>,
+}
+
+pub struct FontStats {
+    /// The name of the font, for display.
+    pub name: String,
+    pub decoded: u64,
+    pub unmapped: BTreeMap<u32, u64>,
+}
+
+impl FontStats {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            decoded: 0,
+            unmapped: BTreeMap::new(),
+        }
+    }
+}
+
+impl DecodeStats {
+    /// The stats of the font that `map` was made from.
+    pub fn font(&mut self, map: &FontMap) -> &mut FontStats {
+        (self.fonts.entry(map.font)).or_insert_with(|| FontStats::new(map.name.clone()))
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        for (font, other) in other.fonts {
+            let stats = match self.fonts.entry(font) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => e.insert(FontStats::new(other.name)),
+            };
+            stats.decoded += other.decoded;
+            for (code, count) in other.unmapped {
+                *stats.unmapped.entry(code).or_default() += count;
+            }
+        }
+    }
+
+    pub fn print_summary(&self) {
+        let fonts = (self.fonts.iter())
+            .filter(|(_, s)| !s.unmapped.is_empty())
+            .sorted_by_key(|(font, s)| (&s.name, font.id, font.gen));
+        for (font, stats) in fonts {
+            let unmapped: u64 = stats.unmapped.values().sum();
+            let codes = stats.unmapped.keys().map(|c| format!("{c:#x}")).join(" ");
+            let name = if stats.name.is_empty() {
+                "Unnamed font"
+            } else {
+                &stats.name
+            };
+            eprintln!(
+                "{name} (object {}): {unmapped} of {} codes unmapped ({codes})",
+                font.id, stats.decoded
+            );
+        }
+    }
+}
+
+/// Byte ranges that make up the codes of a font, as declared by `begincodespacerange` in a CMap.
+/// Simple fonts always use single-byte codes, while Type0 fonts may mix codes of 1 to 4 bytes.
+#[derive(Clone, Debug)]
+pub struct Codespace(Vec<(Vec<u8>, Vec<u8>)>);
+
+impl Codespace {
+    pub fn single_byte() -> Self {
+        Self(vec![range(&[0x00], &[0xff])])
+    }
+
+    pub fn double_byte() -> Self {
+        Self(vec![range(&[0x00, 0x00], &[0xff, 0xff])])
+    }
+
+    pub fn parse_cmap(cmap: &[u8]) -> anyhow::Result<Option<Self>> {
+        let cmap = String::from_utf8_lossy(cmap);
+        let block_regex = Regex::new(r"(?s)begincodespacerange(.*?)endcodespacerange")?;
+        let range_regex = Regex::new(r"<([0-9A-Fa-f\s]*)>\s*<([0-9A-Fa-f\s]*)>")?;
+        let mut ranges = vec![];
+        for block in block_regex.captures_iter(&cmap) {
+            for res in range_regex.captures_iter(&block[1]) {
+                let low = parse_hex(&res[1])?;
+                let high = parse_hex(&res[2])?;
+                if low.is_empty() || low.len() > 4 || low.len() != high.len() {
+                    bail!("Invalid codespace range {:?}", &res[0]);
+                }
+                ranges.push((low, high));
+            }
+        }
+        Ok(if ranges.is_empty() {
+            None
+        } else {
+            Some(Self(ranges))
+        })
+    }
+
+    /// Codespaces of the predefined CMaps (PDF 32000-1:2008, Table 118) that are likely to appear
+    /// in Japanese dictionaries.
+    pub fn predefined(name: &str) -> Option<Self> {
+        Some(Self(
+            if name.starts_with("Identity-") || name.contains("UCS2") {
+                vec![range(&[0x00, 0x00], &[0xff, 0xff])]
+            } else if name.contains("UTF16") {
+                // Surrogate pairs are single four-byte codes
+                vec![
+                    range(&[0x00, 0x00], &[0xd7, 0xff]),
+                    range(&[0xd8, 0x00, 0xdc, 0x00], &[0xdb, 0xff, 0xdf, 0xff]),
+                    range(&[0xe0, 0x00], &[0xff, 0xff]),
+                ]
+            } else if name.contains("RKSJ") {
+                vec![
+                    range(&[0x00], &[0x80]),
+                    range(&[0xa0], &[0xdf]),
+                    range(&[0x81, 0x40], &[0x9f, 0xfc]),
+                    range(&[0xe0, 0x40], &[0xfc, 0xfc]),
+                ]
+            } else if name.contains("EUC") {
+                vec![
+                    range(&[0x00], &[0x80]),
+                    range(&[0x8e, 0xa0], &[0x8e, 0xdf]),
+                    range(&[0xa1, 0xa1], &[0xfe, 0xfe]),
+                ]
+            } else {
+                return None;
+            },
+        ))
+    }
+
+    pub fn codes<'b>(&'b self, mut bytes: &'b [u8]) -> impl Iterator<Item = u32> + 'b {
+        std::iter::from_fn(move || {
+            if bytes.is_empty() {
+                return None;
+            }
+            let (code, rest) = bytes.split_at(self.code_len(bytes));
+            bytes = rest;
+            Some(code.iter().fold(0, |acc, &b| (acc << 8) | u32::from(b)))
+        })
+    }
+
+    fn code_len(&self, bytes: &[u8]) -> usize {
+        let matches = |(low, high): &&(Vec<u8>, Vec<u8>)| {
+            low.len() <= bytes.len()
+                && (low.iter().zip(high).zip(bytes)).all(|((&l, &h), &b)| (l..=h).contains(&b))
+        };
+        // A code outside of every range consumes as many bytes as the shortest range, so that
+        // decoding can continue after it.
+        let range = (self.0.iter().find(matches))
+            .or_else(|| self.0.iter().min_by_key(|(low, _)| low.len()));
+        range.map_or(1, |(low, _)| low.len()).min(bytes.len())
+    }
+}
+
+fn range(low: &[u8], high: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    (low.to_vec(), high.to_vec())
+}
+
+fn parse_hex(s: &str) -> anyhow::Result<Vec<u8>> {
+    let digits = s.chars().filter(|c| !c.is_whitespace()).collect_vec();
+    digits
+        .chunks(2)
+        .map(|c| {
+            let c = c.iter().collect::<String>();
+            u8::from_str_radix(&c, 16).with_context(|| format!("Invalid hex string {s:?}"))
+        })
+        .collect()
+}
+
+pub fn make_unicode_map(
+    file: &pdf::file::File<Vec<u8>>,
+    font: &RcRef<Font>,
+) -> anyhow::Result<FontMap> {
+    Ok(FontMap {
+        font: font.get_ref().get_inner(),
+        name: font.name.as_ref().map_or("", |x| x.as_str()).into(),
+        codespace: make_codespace(file, font)?,
+        map: make_code_map(file, font)?,
+    })
+}
+
+fn make_codespace(
+    file: &pdf::file::File<Vec<u8>>,
+    font: &RcRef<Font>,
+) -> anyhow::Result<Codespace> {
+    if !matches!(font.subtype, FontType::Type0) {
+        return Ok(Codespace::single_byte());
+    }
+    if let Some(cmap) = embedded_cmap(file, font)? {
+        if let Some(codespace) = Codespace::parse_cmap(&cmap)? {
+            return Ok(codespace);
+        }
+    }
+    // The ToUnicode CMap usually repeats the codespace of the encoding CMap.
+    if let Some(to_unicode) = &font.to_unicode {
+        if let Some(codespace) = Codespace::parse_cmap(&to_unicode.data(file)?)? {
+            return Ok(codespace);
+        }
+    }
+    Ok(match font.encoding() {
+        Some(Encoding {
+            base: BaseEncoding::Other(name),
+            ..
+        }) => Codespace::predefined(name.as_str()).unwrap_or_else(Codespace::double_byte),
+        _ => Codespace::double_byte(),
     })
 }
 
-fn make_unicode_map(
+/// The encoding CMap embedded in a Type0 font.  [`Font`] only keeps the name of a predefined
+/// CMap, so the stream is looked up in the font dictionary.
+fn embedded_cmap(
     file: &pdf::file::File<Vec<u8>>,
     font: &RcRef<Font>,
-) -> anyhow::Result<HashMap<u16, String>> {
-    match font.name.as_ref().map(|x| x.as_str()) {
+) -> anyhow::Result<Option<Arc<[u8]>>> {
+    let dict = file
+        .resolve(font.get_ref().get_inner())?
+        .into_dictionary()?;
+    Ok(match dict.get("Encoding") {
+        Some(&Primitive::Reference(cmap)) => {
+            Some(file.get(Ref::<Stream<()>>::new(cmap))?.data(file)?)
+        }
+        _ => None,
+    })
+}
+
+fn make_code_map(
+    file: &pdf::file::File<Vec<u8>>,
+    font: &RcRef<Font>,
+) -> anyhow::Result<HashMap<u32, String>> {
+    if let Some(map) = font
+        .name
+        .as_ref()
+        .and_then(|x| hardcoded_code_map(x.as_str()))
+    {
+        return Ok(map);
+    }
+    if let Some(map) = font.to_unicode(file).transpose()? {
+        Ok(map.iter().map(|(k, v)| (k.into(), v.into())).collect())
+    } else if let Some(mut codes) = base_encoding(font) {
+        if let Some(encoding) = font.encoding() {
+            codes
+                .extend((encoding.differences.iter()).map(|(&k, v)| (k, glyph_name_to_unicode(v))));
+        }
+        Ok(codes)
+    } else {
+        bail!("Cannot generate ToUnicode map from {font:?}")
+    }
+}
+
+/// The text of each code of a simple font's base encoding, before the differences are applied.
+/// `None` if the codes can't be mapped from the encoding.
+fn base_encoding(font: &Font) -> Option<HashMap<u32, String>> {
+    if !matches!(
+        font.subtype,
+        FontType::TrueType | FontType::Type1 | FontType::MMType1 | FontType::Type3
+    ) {
+        return None;
+    }
+    Some(match (font.subtype, font.encoding().map(|e| &e.base)) {
+        (_, Some(BaseEncoding::WinAnsiEncoding)) => win_ansi_encoding(),
+        (_, Some(BaseEncoding::MacRomanEncoding)) => mac_roman_encoding(),
+        (_, Some(BaseEncoding::StandardEncoding)) => standard_encoding(),
+        // Type1 fonts without a base encoding use the built-in encoding of the font program,
+        // which is the standard encoding for every text font we have seen so far.
+        (FontType::Type1 | FontType::MMType1, None | Some(BaseEncoding::None)) => {
+            standard_encoding()
+        }
+        // Type3 glyphs are only reachable through the differences.
+        (FontType::Type3, Some(BaseEncoding::None)) => HashMap::new(),
+        _ => return None,
+    })
+}
+
+/// A code that the `/Differences` of a font's encoding give another glyph.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EncodingDifference {
+    pub code: u32,
+    /// The text of the code in the base encoding, if it has one there.
+    pub base: Option<String>,
+    pub glyph_name: String,
+    /// The text of the glyph, which replaces `base`.
+    pub text: String,
+}
+
+/// The differences of the font's encoding by code, each decoded both with the base encoding and
+/// as the glyph it is changed to.
+pub fn encoding_differences(font: &Font) -> Vec<EncodingDifference> {
+    let base = base_encoding(font).unwrap_or_default();
+    match font.encoding() {
+        Some(encoding) => differences(&base, &encoding.differences),
+        None => vec![],
+    }
+}
+
+fn differences(
+    base: &HashMap<u32, String>,
+    differences: &HashMap<u32, String>,
+) -> Vec<EncodingDifference> {
+    differences
+        .iter()
+        .map(|(&code, glyph_name)| EncodingDifference {
+            code,
+            base: base.get(&code).cloned(),
+            glyph_name: glyph_name.clone(),
+            text: glyph_name_to_unicode(glyph_name),
+        })
+        .sorted_by_key(|difference| difference.code)
+        .collect()
+}
+
+/// Code maps of fonts that don't map their codes to Unicode themselves, or map them wrongly.
+fn hardcoded_code_map(name: &str) -> Option<HashMap<u32, String>> {
+    match name {
         // Embedded gaiji font.  No way to get these mapping from file so we hardcode them.
-        Some("DXNKCI+GaijiL") => {
-            return Ok(maplit::hashmap![
+        "DXNKCI+GaijiL" => {
+            Some(maplit::hashmap![
                 65 => "\u{227}".into(),
                 67 => "ᒑ".into(), // similar to [j]?  no such glyph in unicode
                 68 => ";".into(), // long vowel with stød, no such glyph in unicode
                 69 => "\u{283}".into(),
-            ]);
-        }
-        Some("NZLSMO+GaijiL2") => {
-            return Ok(maplit::hashmap![
-                76 => "\u{329}".into(),
-            ]);
+            ])
         }
+        "NZLSMO+GaijiL2" => Some(maplit::hashmap![
+            76 => "\u{329}".into(),
+        ]),
         // Patch IPA font that uses private use area to standard Unicode phonetic alphabet for
         // visualization purpose.
-        Some("DXNKCI+Ipa-samdUclphon1SILDoulosL") => {
-            return Ok(maplit::hashmap![
+        "DXNKCI+Ipa-samdUclphon1SILDoulosL" => {
+            Some(maplit::hashmap![
                 // 【？】
                 4 => "ˈ".into(),
                 7 => "ˌ".into(),
@@ -82,54 +503,369 @@ fn make_unicode_map(
                 229 => "【？】".into(),
                 254 => "【？】".into(),
                 256 => "【？】".into(),
-            ]);
+            ])
         }
-        _ => {}
-    };
-    if let Some(map) = font.to_unicode(file).transpose()? {
-        Ok(map.iter().map(|(k, v)| (k, v.into())).collect())
-    } else if let (
-        FontType::TrueType,
-        Some(Encoding {
-            base: BaseEncoding::WinAnsiEncoding,
-            differences,
-        }),
-    ) = (font.subtype, font.encoding())
+        _ => None,
+    }
+}
+
+/// Whether the codes of the font named `name` are mapped by a table in this crate instead of by
+/// the font.
+pub fn has_hardcoded_code_map(name: &str) -> bool {
+    hardcoded_code_map(name).is_some()
+}
+
+fn ascii_encoding() -> HashMap<u32, String> {
+    (32..127u8)
+        .map(|code| (code.into(), (code as char).to_string()))
+        .collect()
+}
+
+fn win_ansi_encoding() -> HashMap<u32, String> {
+    // Based on https://github.com/kaj/rust-pdf/blob/master/src/encoding.rs
+    let mut codes = HashMap::new();
+    for code in 32..255u8 {
+        codes.insert(code.into(), (code as char).to_string());
+    }
+    codes.insert(128, "€".into());
+    codes.insert(130, "‚".into());
+    codes.insert(131, "ƒ".into());
+    codes.insert(132, "„".into());
+    codes.insert(133, "…".into());
+    codes.insert(134, "†".into());
+    codes.insert(135, "‡".into());
+    codes.insert(136, "ˆ".into());
+    codes.insert(137, "‰".into());
+    codes.insert(138, "Š".into());
+    codes.insert(139, "‹".into());
+    codes.insert(140, "Œ".into());
+    codes.insert(142, "Ž".into());
+    codes.insert(145, "‘".into());
+    codes.insert(146, "’".into());
+    codes.insert(147, "“".into());
+    codes.insert(148, "”".into());
+    codes.insert(149, "•".into());
+    codes.insert(150, "–".into());
+    codes.insert(151, "—".into());
+    codes.insert(152, "˜".into());
+    codes.insert(153, "™".into());
+    codes.insert(154, "š".into());
+    codes.insert(155, "›".into());
+    codes.insert(158, "ž".into());
+    codes.insert(159, "Ÿ".into());
+    codes
+}
+
+fn mac_roman_encoding() -> HashMap<u32, String> {
+    // Codes 128..=255; \0 marks the undefined 0xF0.
+    let high = "ÄÅÇÉÑÖÜáàâäãåçéèêëíìîïñóòôöõúùûü†°¢£§•¶ß®©™´¨≠ÆØ∞±≤≥¥µ∂∑∏π∫ªºΩæø\
+        ¿¡¬√ƒ≈∆«»…\u{a0}ÀÃÕŒœ–—“”‘’÷◊ÿŸ⁄¤‹›ﬁﬂ‡·‚„‰ÂÊÁËÈÍÎÏÌÓÔ\0ÒÚÛÙıˆ˜¯˘˙˚¸˝˛ˇ";
+    let mut codes = ascii_encoding();
+    extend_with_table(&mut codes, 128, high);
+    codes
+}
+
+fn standard_encoding() -> HashMap<u32, String> {
+    let mut codes = ascii_encoding();
+    codes.insert(0x27, "’".into());
+    codes.insert(0x60, "‘".into());
+    // Codes 160..=255; \0 marks undefined codes.
+    let high = "\0¡¢£⁄¥ƒ§¤'“«‹›ﬁﬂ\0–†‡·\0¶•‚„”»…‰\0¿\0`´ˆ˜¯˘˙¨\0˚¸\0˝˛ˇ\
+        —\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0Æ\0ª\0\0\0\0ŁØŒº\0\0\0\0\0æ\0\0\0ı\0\0łøœß\0\0\0\0";
+    extend_with_table(&mut codes, 160, high);
+    codes
+}
+
+fn parse_uni_glyph_name(hex: &str) -> Option<String> {
+    if hex.is_empty() || hex.len() % 4 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(4)
+        .map(|i| char::from_u32(u32::from_str_radix(hex.get(i..i + 4)?, 16).ok()?))
+        .collect()
+}
+
+/// Adds the characters of `table` for consecutive codes starting at `first`, skipping `\0`.
+fn extend_with_table(codes: &mut HashMap<u32, String>, first: u32, table: &str) {
+    codes.extend(
+        (first..)
+            .zip(table.chars())
+            .filter(|&(_, c)| c != '\0')
+            .map(|(code, c)| (code, c.to_string())),
+    );
+}
+
+/// Converts a glyph name in `/Differences` to text, following the Adobe Glyph List conventions
+/// for the names that are likely to appear in a Danish dictionary.
+fn glyph_name_to_unicode(name: &str) -> String {
+    if let Some(chars) = name.strip_prefix("uni").and_then(parse_uni_glyph_name) {
+        return chars;
+    }
+    if let Some(c) = (name.strip_prefix('u'))
+        .filter(|hex| (4..=6).contains(&hex.len()))
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .and_then(char::from_u32)
     {
-        // Based on https://github.com/kaj/rust-pdf/blob/master/src/encoding.rs
-        let mut codes = HashMap::new();
-        for code in 32..255u8 {
-            codes.insert(code as u16, (code as char).to_string());
-        }
-        codes.insert(128, "€".into());
-        codes.insert(130, "‚".into());
-        codes.insert(131, "ƒ".into());
-        codes.insert(132, "„".into());
-        codes.insert(133, "…".into());
-        codes.insert(134, "†".into());
-        codes.insert(135, "‡".into());
-        codes.insert(136, "ˆ".into());
-        codes.insert(137, "‰".into());
-        codes.insert(138, "Š".into());
-        codes.insert(139, "‹".into());
-        codes.insert(140, "Œ".into());
-        codes.insert(142, "Ž".into());
-        codes.insert(145, "‘".into());
-        codes.insert(146, "’".into());
-        codes.insert(147, "“".into());
-        codes.insert(148, "”".into());
-        codes.insert(149, "•".into());
-        codes.insert(150, "–".into());
-        codes.insert(151, "—".into());
-        codes.insert(152, "˜".into());
-        codes.insert(153, "™".into());
-        codes.insert(154, "š".into());
-        codes.insert(155, "›".into());
-        codes.insert(158, "ž".into());
-        codes.insert(159, "Ÿ".into());
-        codes.extend(differences.iter().map(|(&k, v)| (k as u16, v.into())));
-        Ok(codes)
-    } else {
-        bail!("Cannot generate ToUnicode map from {font:?}")
+        return c.to_string();
+    }
+    let c = match name {
+        "space" | "nbspace" => ' ',
+        "exclam" => '!',
+        "quotedbl" => '"',
+        "numbersign" => '#',
+        "dollar" => '$',
+        "percent" => '%',
+        "ampersand" => '&',
+        "quotesingle" => '\'',
+        "parenleft" => '(',
+        "parenright" => ')',
+        "asterisk" => '*',
+        "plus" => '+',
+        "comma" => ',',
+        "hyphen" | "minus" => '-',
+        "period" => '.',
+        "slash" => '/',
+        "zero" => '0',
+        "one" => '1',
+        "two" => '2',
+        "three" => '3',
+        "four" => '4',
+        "five" => '5',
+        "six" => '6',
+        "seven" => '7',
+        "eight" => '8',
+        "nine" => '9',
+        "colon" => ':',
+        "semicolon" => ';',
+        "less" => '<',
+        "equal" => '=',
+        "greater" => '>',
+        "question" => '?',
+        "at" => '@',
+        "bracketleft" => '[',
+        "backslash" => '\\',
+        "bracketright" => ']',
+        "asciicircum" => '^',
+        "underscore" => '_',
+        "grave" => '`',
+        "braceleft" => '{',
+        "bar" => '|',
+        "braceright" => '}',
+        "asciitilde" => '~',
+        "quoteleft" => '‘',
+        "quoteright" => '’',
+        "quotedblleft" => '“',
+        "quotedblright" => '”',
+        "quotesinglbase" => '‚',
+        "quotedblbase" => '„',
+        "endash" => '–',
+        "emdash" => '—',
+        "bullet" => '•',
+        "ellipsis" => '…',
+        "periodcentered" => '·',
+        "section" => '§',
+        "paragraph" => '¶',
+        "degree" => '°',
+        "guillemotleft" => '«',
+        "guillemotright" => '»',
+        "aring" => 'å',
+        "Aring" => 'Å',
+        "ae" => 'æ',
+        "AE" => 'Æ',
+        "oslash" => 'ø',
+        "Oslash" => 'Ø',
+        "adieresis" => 'ä',
+        "Adieresis" => 'Ä',
+        "odieresis" => 'ö',
+        "Odieresis" => 'Ö',
+        "udieresis" => 'ü',
+        "Udieresis" => 'Ü',
+        "aacute" => 'á',
+        "eacute" => 'é',
+        "Eacute" => 'É',
+        "egrave" => 'è',
+        "iacute" => 'í',
+        "oacute" => 'ó',
+        "uacute" => 'ú',
+        "yacute" => 'ý',
+        "ccedilla" => 'ç',
+        "ntilde" => 'ñ',
+        "germandbls" => 'ß',
+        "acute" => '´',
+        "dieresis" => '¨',
+        "ring" => '˚',
+        "fi" => 'ﬁ',
+        "fl" => 'ﬂ',
+        _ => return name.into(),
+    };
+    c.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(codespace: &Codespace, bytes: &[u8]) -> Vec<u32> {
+        codespace.codes(bytes).collect()
+    }
+
+    #[test]
+    fn splits_bytes_into_codes_of_the_codespace() {
+        assert_eq!(codes(&Codespace::single_byte(), b"\x41\x82"), [0x41, 0x82]);
+        assert_eq!(
+            codes(&Codespace::double_byte(), b"\x30\x42\x00\x41"),
+            [0x3042, 0x41]
+        );
+
+        // Shift-JIS mixes single-byte ASCII and half-width katakana with double-byte kanji
+        let rksj = Codespace::predefined("90ms-RKSJ-H").unwrap();
+        assert_eq!(
+            codes(&rksj, b"a\xb1\x88\x9f\xe0\x40"),
+            [0x61, 0xb1, 0x889f, 0xe040]
+        );
+        // A byte outside every range is a code of the shortest length, and a truncated code at
+        // the end is a code of the bytes that are left
+        assert_eq!(codes(&rksj, b"\xfd\x81"), [0xfd, 0x81]);
+
+        let utf16 = Codespace::predefined("UniJIS-UTF16-H").unwrap();
+        assert_eq!(
+            codes(&utf16, b"\x30\x42\xd8\x40\xdc\x0b\xe0\x00"),
+            [0x3042, 0xd840dc0b, 0xe000]
+        );
+    }
+
+    #[test]
+    fn parses_codespace_ranges_of_a_cmap() {
+        let cmap = b"/CMapName /Test def\n\
+            2 begincodespacerange\n<00> <80>\n<8140> <9FFC>\nendcodespacerange\n\
+            1 begincodespacerange <E040> <FCFC> endcodespacerange\n\
+            1 beginbfchar <41> <0041> endbfchar";
+        let codespace = Codespace::parse_cmap(cmap).unwrap().unwrap();
+        assert_eq!(
+            codes(&codespace, b"\x41\x81\x40\xe0\x40"),
+            [0x41, 0x8140, 0xe040]
+        );
+
+        assert!(
+            Codespace::parse_cmap(b"1 beginbfchar <41> <0041> endbfchar")
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            Codespace::parse_cmap(b"begincodespacerange <00> <FFFF> endcodespacerange").is_err()
+        );
+    }
+
+    #[test]
+    fn stats_tell_fonts_with_the_same_name_apart() {
+        let map = |id| FontMap {
+            font: PlainRef { id, gen: 0 },
+            name: "Ryumin-Light".into(),
+            codespace: Codespace::single_byte(),
+            map: HashMap::new(),
+        };
+        let mut stats = DecodeStats::default();
+        stats.font(&map(3)).decoded += 1;
+        stats.font(&map(7)).decoded += 2;
+        let mut other = DecodeStats::default();
+        *other.font(&map(7)).unmapped.entry(0x41).or_default() += 1;
+        stats.merge(other);
+
+        assert_eq!(stats.fonts.len(), 2);
+        let font = &stats.fonts[&PlainRef { id: 7, gen: 0 }];
+        assert_eq!(font.name, "Ryumin-Light");
+        assert_eq!(font.decoded, 2);
+        assert_eq!(font.unmapped, BTreeMap::from([(0x41, 1)]));
+    }
+
+    #[test]
+    fn decodes_differences_with_the_base_encoding_and_the_glyph() {
+        let glyphs = HashMap::from([
+            (0xa9, "uni3042".to_owned()),
+            (0x27, "quotesingle".to_owned()),
+            (0x01, "g12".to_owned()),
+        ]);
+        let difference =
+            |code, base: Option<&str>, glyph_name: &str, text: &str| EncodingDifference {
+                code,
+                base: base.map(str::to_owned),
+                glyph_name: glyph_name.to_owned(),
+                text: text.to_owned(),
+            };
+        assert_eq!(
+            differences(&standard_encoding(), &glyphs),
+            [
+                // Names without a known Unicode value are kept as they are
+                difference(0x01, None, "g12", "g12"),
+                difference(0x27, Some("’"), "quotesingle", "'"),
+                difference(0xa9, Some("'"), "uni3042", "あ"),
+            ]
+        );
+        assert_eq!(differences(&standard_encoding(), &HashMap::new()), []);
+    }
+
+    fn decode(codes: &HashMap<u32, String>, code: u32) -> Option<&str> {
+        codes.get(&code).map(String::as_str)
+    }
+
+    #[test]
+    fn standard_encoding_table() {
+        let codes = standard_encoding();
+        // The 149 codes that StandardEncoding defines (PDF 32000-1:2008, Annex D.2)
+        assert_eq!(codes.len(), 149);
+        for (code, text) in [
+            (0x27, "’"),
+            (0x41, "A"),
+            (0x60, "‘"),
+            (0xa1, "¡"),
+            (0xa4, "⁄"),
+            (0xa9, "'"),
+            (0xae, "ﬁ"),
+            (0xb1, "–"),
+            (0xc1, "`"),
+            (0xca, "˚"),
+            (0xcf, "ˇ"),
+            (0xd0, "—"),
+            (0xe1, "Æ"),
+            (0xe9, "Ø"),
+            (0xf1, "æ"),
+            (0xf5, "ı"),
+            (0xf9, "ø"),
+            (0xfb, "ß"),
+        ] {
+            assert_eq!(decode(&codes, code), Some(text), "{code:#x}");
+        }
+        for code in [0xa0, 0xb0, 0xc0, 0xd1, 0xe0, 0xff] {
+            assert_eq!(decode(&codes, code), None, "{code:#x}");
+        }
+    }
+
+    #[test]
+    fn mac_roman_encoding_table() {
+        let codes = mac_roman_encoding();
+        // Every code from space on except DEL and the Apple logo
+        assert_eq!(codes.len(), 95 + 127);
+        for (code, text) in [
+            (0x41, "A"),
+            (0x80, "Ä"),
+            (0x81, "Å"),
+            (0x8c, "å"),
+            (0xa0, "†"),
+            (0xae, "Æ"),
+            (0xaf, "Ø"),
+            (0xbe, "æ"),
+            (0xbf, "ø"),
+            (0xca, "\u{a0}"),
+            (0xdb, "¤"),
+            (0xde, "ﬁ"),
+            (0xef, "Ô"),
+            (0xf1, "Ò"),
+            (0xf5, "ı"),
+            (0xff, "ˇ"),
+        ] {
+            assert_eq!(decode(&codes, code), Some(text), "{code:#x}");
+        }
+        assert_eq!(decode(&codes, 0xf0), None);
     }
 }
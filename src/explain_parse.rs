@@ -0,0 +1,253 @@
+use std::{fmt, ops::Range};
+
+use regex::Regex;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Number of columns of text shown before and after the offset where matching stops, so that
+/// the caret stays under it on a narrow terminal.
+const CONTEXT_BEFORE: usize = 40;
+const CONTEXT_AFTER: usize = 30;
+
+/// A part of the entry grammar, in the order the parts appear in an entry.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Component {
+    Headword,
+    Homograph,
+    PartsOfSpeech,
+    Pronunciations,
+    /// `[不変化]`, marking an adjective that isn't inflected.
+    Invariant,
+    /// `en` after the pronunciations.
+    Gender,
+    OtherForms,
+    OtherAdjectiveForms,
+    /// `(en)` before the colon.
+    ParenthesizedGender,
+    Colon,
+}
+
+impl Component {
+    pub(crate) fn optional(self) -> bool {
+        !matches!(
+            self,
+            Component::Headword | Component::Pronunciations | Component::Colon
+        )
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Component::Headword => "headword",
+            Component::Homograph => "homograph number",
+            Component::PartsOfSpeech => "part of speech list",
+            Component::Pronunciations => "pronunciation list",
+            Component::Invariant => "invariant marker",
+            Component::Gender => "gender marker `en`",
+            Component::OtherForms => "other forms",
+            Component::OtherAdjectiveForms => "other adjective forms",
+            Component::ParenthesizedGender => "gender marker `(en)`",
+            Component::Colon => "colon",
+        })
+    }
+}
+
+/// How far an entry matches the entry grammar when its parts are matched one at a time.
+pub struct Explanation<'a> {
+    pub text: &'a str,
+    /// The parts that matched, with their byte ranges in `text`.
+    pub matched: Vec<(Component, Range<usize>)>,
+    /// The byte offset where matching stopped and the part that was expected there, or `None`
+    /// if every part matched.
+    pub failure: Option<(usize, Component)>,
+}
+
+/// Matches each of `steps` at the end of the previous match, skipping the optional parts that
+/// don't match.  Each part is matched greedily on its own, whereas the entry regex can backtrack
+/// into a part to match the next, so the parts can match further than the whole regex does.
+pub fn explain<'a>(steps: &[(Component, Regex)], text: &'a str) -> Explanation<'a> {
+    let mut offset = 0;
+    let mut matched = vec![];
+    for &(component, ref regex) in steps {
+        match regex.find(&text[offset..]) {
+            Some(m) if !m.as_str().is_empty() => {
+                matched.push((component, offset..offset + m.end()));
+                offset += m.end();
+            }
+            _ if component.optional() => {}
+            _ => {
+                return Explanation {
+                    text,
+                    matched,
+                    failure: Some((offset, component)),
+                }
+            }
+        }
+    }
+    Explanation {
+        text,
+        matched,
+        failure: None,
+    }
+}
+
+/// Shows where matching stopped like a compiler error, with a caret under the offset:
+///
+/// ```text
+/// expected colon after pronunciation list " [ˈsαmˌsø;] " at column 22
+///   | Samsø [固] [ˈsαmˌsø;] サムスー島．
+///   |                       ^
+/// ```
+impl fmt::Display for Explanation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (offset, expected) = match self.failure {
+            Some(failure) => failure,
+            None => return write!(f, "every part of the entry grammar matches"),
+        };
+        write!(f, "expected {expected}")?;
+        if let Some((component, range)) = self.matched.last() {
+            write!(f, " after {component} {:?}", &self.text[range.clone()])?;
+        }
+        writeln!(f, " at column {}", self.text[..offset].chars().count() + 1)?;
+
+        let start = window_start(self.text, offset);
+        let end = window_end(self.text, offset);
+        let before = format!(
+            "{}{}",
+            if start > 0 { "…" } else { "" },
+            &self.text[start..offset]
+        );
+        let after = format!(
+            "{}{}",
+            &self.text[offset..end],
+            if end < self.text.len() { "…" } else { "" }
+        );
+        writeln!(f, "  | {before}{after}")?;
+        write!(f, "  | {:width$}^", "", width = before.width())
+    }
+}
+
+/// The byte offset of the first char shown before `offset`.
+fn window_start(text: &str, offset: usize) -> usize {
+    let mut width = 0;
+    for (i, c) in text[..offset].char_indices().rev() {
+        width += c.width().unwrap_or(0);
+        if width > CONTEXT_BEFORE {
+            return i + c.len_utf8();
+        }
+    }
+    0
+}
+
+/// The byte offset after the last char shown after `offset`.
+fn window_end(text: &str, offset: usize) -> usize {
+    let mut width = 0;
+    for (i, c) in text[offset..].char_indices() {
+        width += c.width().unwrap_or(0);
+        if width > CONTEXT_AFTER {
+            return offset + i;
+        }
+    }
+    text.len()
+}
+
+/// Prints the parts of the entry that matched, one per line, then where matching stopped.
+pub fn print_explanation(explanation: &Explanation) {
+    println!("{}", explanation.text);
+    for (component, range) in &explanation.matched {
+        println!(
+            "  {:24}{:?}",
+            component.to_string(),
+            &explanation.text[range.clone()]
+        );
+    }
+    println!("{explanation}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_dictionary::DictionaryParser;
+
+    #[test]
+    fn stops_at_the_first_part_that_doesnt_match() {
+        let parser = DictionaryParser::new().unwrap();
+        let text = "Samsø [固] [ˈsαmˌsø;] サムスー島．";
+        let explanation = parser.explain(text);
+        let matched = (explanation.matched.iter())
+            .map(|(component, range)| (*component, &text[range.clone()]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            matched,
+            [
+                (Component::Headword, "Samsø"),
+                (Component::PartsOfSpeech, " [固]"),
+                (Component::Pronunciations, " [ˈsαmˌsø;] "),
+            ]
+        );
+        assert_eq!(
+            explanation.failure,
+            Some((text.find('サ').unwrap(), Component::Colon))
+        );
+        assert_eq!(
+            explanation.to_string(),
+            "expected colon after pronunciation list \" [ˈsαmˌsø;] \" at column 22\n\
+             \x20 | Samsø [固] [ˈsαmˌsø;] サムスー島．\n\
+             \x20 |                       ^"
+        );
+
+        let explanation = parser.explain("knuse [動] [ˈknu:sə]: 壊す．");
+        assert_eq!(explanation.failure, None);
+        assert_eq!(explanation.matched.len(), 4);
+    }
+
+    #[test]
+    fn caret_counts_wide_characters_twice() {
+        let explanation = Explanation {
+            text: "ああ[",
+            matched: vec![(Component::Headword, 0..6)],
+            failure: Some((6, Component::Pronunciations)),
+        };
+        assert_eq!(
+            explanation.to_string(),
+            "expected pronunciation list after headword \"ああ\" at column 3\n\
+             \x20 | ああ[\n\
+             \x20 |     ^"
+        );
+    }
+
+    #[test]
+    fn windows_are_measured_in_columns() {
+        let ascii = "a".repeat(200);
+        assert_eq!(window_start(&ascii, 100), 100 - CONTEXT_BEFORE);
+        assert_eq!(window_end(&ascii, 100), 100 + CONTEXT_AFTER);
+        assert_eq!(window_start(&ascii, 10), 0);
+        assert_eq!(window_end(&ascii, 190), 200);
+
+        // Two columns and three bytes each
+        let wide = "あ".repeat(100);
+        let offset = 50 * 3;
+        assert_eq!(window_start(&wide, offset), offset - CONTEXT_BEFORE / 2 * 3);
+        assert_eq!(window_end(&wide, offset), offset + CONTEXT_AFTER / 2 * 3);
+        let start = window_start(&wide, offset);
+        assert_eq!(wide[start..offset].width(), CONTEXT_BEFORE);
+    }
+
+    #[test]
+    fn long_lines_are_cut_around_the_caret() {
+        let text = format!("{} [名] {}", "a".repeat(60), "ø".repeat(60));
+        let explanation = Explanation {
+            text: &text,
+            matched: vec![],
+            failure: Some((61, Component::PartsOfSpeech)),
+        };
+        let rendered = explanation.to_string();
+        let lines = rendered.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[1],
+            format!("  | …{} [名] {}…", "a".repeat(39), "ø".repeat(25))
+        );
+        // The caret is under the `[`, after the ellipsis and 40 columns of context
+        assert_eq!(lines[2], format!("  | {}^", " ".repeat(1 + CONTEXT_BEFORE)));
+    }
+}
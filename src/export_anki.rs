@@ -0,0 +1,423 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use itertools::Itertools;
+use rusqlite::{params, Connection, Transaction};
+use serde_json::{json, Value};
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::{
+    inflection::slots,
+    output::{escape_html, form_cell, pos_label},
+    parse_dictionary::{EntryBuf, Pos},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum NoteType {
+    /// Danish word on the front, Japanese definition on the back
+    DaJa,
+    /// Japanese definition on the front, Danish word on the back
+    JaDa,
+    /// One card per inflected form of the verbs and nouns whose slot in the paradigm is known
+    Inflection,
+}
+
+impl NoteType {
+    /// Model ids have to stay the same between exports, otherwise Anki imports a new note type.
+    fn model_id(self) -> i64 {
+        match self {
+            NoteType::DaJa => 1_660_000_000_001,
+            NoteType::JaDa => 1_660_000_000_002,
+            NoteType::Inflection => 1_660_000_000_003,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            NoteType::DaJa => "Danish → Japanese",
+            NoteType::JaDa => "Japanese → Danish",
+            NoteType::Inflection => "Danish inflection",
+        }
+    }
+
+    fn fields(self) -> &'static [&'static str] {
+        match self {
+            NoteType::DaJa | NoteType::JaDa => &[
+                "Word",
+                "PartOfSpeech",
+                "Pronunciation",
+                "Forms",
+                "Definition",
+            ],
+            NoteType::Inflection => &["Word", "Definition", "Slot", "Form", "Pronunciation"],
+        }
+    }
+
+    /// `(front, back)`
+    fn templates(self) -> (&'static str, &'static str) {
+        match self {
+            NoteType::DaJa => (
+                "<div class=word>{{Word}}</div><div class=pos>{{PartOfSpeech}}</div>",
+                "{{FrontSide}}<hr id=answer>\
+                 <div class=ipa>{{Pronunciation}}</div>\
+                 <div class=definition>{{Definition}}</div>\
+                 <div class=forms>{{Forms}}</div>",
+            ),
+            NoteType::JaDa => (
+                "<div class=definition>{{Definition}}</div><div class=pos>{{PartOfSpeech}}</div>",
+                "{{FrontSide}}<hr id=answer>\
+                 <div class=word>{{Word}}</div>\
+                 <div class=ipa>{{Pronunciation}}</div>\
+                 <div class=forms>{{Forms}}</div>",
+            ),
+            NoteType::Inflection => (
+                "<div class=word>{{Word}}</div><div class=definition>{{Definition}}</div>\
+                 <div class=slot>{{Slot}}?</div>",
+                "{{FrontSide}}<hr id=answer>\
+                 <div class=word>{{Form}}</div><div class=ipa>{{Pronunciation}}</div>",
+            ),
+        }
+    }
+}
+
+const CSS: &str = ".card { font-family: sans-serif; font-size: 24px; text-align: center; }
+.pos, .ipa, .forms, .slot { font-size: 18px; color: #666; }
+";
+
+const SCHEMA: &str = "
+    CREATE TABLE col (
+        id integer primary key, crt integer not null, mod integer not null,
+        scm integer not null, ver integer not null, dty integer not null, usn integer not null,
+        ls integer not null, conf text not null, models text not null, decks text not null,
+        dconf text not null, tags text not null
+    );
+    CREATE TABLE notes (
+        id integer primary key, guid text not null, mid integer not null, mod integer not null,
+        usn integer not null, tags text not null, flds text not null, sfld integer not null,
+        csum integer not null, flags integer not null, data text not null
+    );
+    CREATE TABLE cards (
+        id integer primary key, nid integer not null, did integer not null, ord integer not null,
+        mod integer not null, usn integer not null, type integer not null,
+        queue integer not null, due integer not null, ivl integer not null,
+        factor integer not null, reps integer not null, lapses integer not null,
+        left integer not null, odue integer not null, odid integer not null,
+        flags integer not null, data text not null
+    );
+    CREATE TABLE revlog (
+        id integer primary key, cid integer not null, usn integer not null,
+        ease integer not null, ivl integer not null, lastIvl integer not null,
+        factor integer not null, time integer not null, type integer not null
+    );
+    CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+    CREATE INDEX ix_notes_usn on notes (usn);
+    CREATE INDEX ix_cards_usn on cards (usn);
+    CREATE INDEX ix_revlog_usn on revlog (usn);
+    CREATE INDEX ix_cards_nid on cards (nid);
+    CREATE INDEX ix_cards_sched on cards (did, queue, due);
+    CREATE INDEX ix_revlog_cid on revlog (cid);
+    CREATE INDEX ix_notes_csum on notes (csum);
+";
+
+/// Writes an Anki package with one deck containing notes of each of `note_types`.
+///
+/// The GUID of a note is derived from the note type, the headword and its homograph number (and
+/// the slot and form for inflection cards), so importing a newer export updates the existing
+/// notes.  A repeated headword without a homograph number also gets the number of its occurrence.
+pub fn export_anki(
+    path: &Path,
+    deck_name: &str,
+    note_types: &[NoteType],
+    entries: &[EntryBuf],
+) -> anyhow::Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    let now_ms = i64::try_from(now.as_millis())?;
+    let now_s = i64::try_from(now.as_secs())?;
+    let deck_id = stable_id(deck_name);
+
+    let collection = path.with_extension("anki2.tmp");
+    if collection.exists() {
+        fs_err::remove_file(&collection)?;
+    }
+    {
+        let mut conn = Connection::open(&collection)?;
+        conn.execute_batch(SCHEMA)?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO col VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+            params![
+                now_s,
+                now_ms,
+                collection_conf(deck_id).to_string(),
+                models(note_types, deck_id, now_s).to_string(),
+                decks(deck_id, deck_name, now_s).to_string(),
+                deck_conf().to_string(),
+            ],
+        )?;
+        let occurrences = occurrences(entries);
+        let mut position = 1;
+        for &note_type in note_types {
+            for (entry, &occurrence) in entries.iter().zip(&occurrences) {
+                for (guid, fields) in notes(note_type, entry, occurrence) {
+                    let id = now_ms + position;
+                    add_note(&tx, note_type, deck_id, &guid, &fields, id, position, now_s)?;
+                    position += 1;
+                }
+            }
+        }
+        tx.commit()?;
+    }
+
+    let mut zip = ZipWriter::new(fs_err::File::create(path)?);
+    zip.start_file("collection.anki2", FileOptions::default())?;
+    zip.write_all(&fs_err::read(&collection)?)?;
+    // No media, but Anki expects the manifest
+    zip.start_file("media", FileOptions::default())?;
+    zip.write_all(b"{}")?;
+    zip.finish()?;
+    fs_err::remove_file(&collection)?;
+    Ok(())
+}
+
+/// A positive id that only depends on `s`.
+fn stable_id(s: &str) -> i64 {
+    let digest = sha1_smol::Sha1::from(s).digest().bytes();
+    let mut id = [0; 8];
+    id.copy_from_slice(&digest[..8]);
+    (i64::from_be_bytes(id) & 0x7fff_ffff_ffff) | 1
+}
+
+/// How many entries before and including each entry have its headword and homograph number.
+/// Headwords without a homograph number may repeat.
+fn occurrences(entries: &[EntryBuf]) -> Vec<usize> {
+    let mut counts = HashMap::new();
+    entries
+        .iter()
+        .map(|entry| {
+            let count = counts.entry((&entry.word, entry.homograph)).or_insert(0);
+            *count += 1;
+            *count
+        })
+        .collect()
+}
+
+/// `(guid, fields)` of the notes of this type for `entry`, which is the `occurrence`th entry with
+/// its headword and homograph number.
+fn notes(note_type: NoteType, entry: &EntryBuf, occurrence: usize) -> Vec<(String, Vec<String>)> {
+    let homograph = entry.homograph.map_or(String::new(), |h| h.to_string());
+    let mut key = format!("{:?}\u{1f}{}\u{1f}{homograph}", note_type, entry.word);
+    if occurrence > 1 {
+        key += &format!("\u{1f}{occurrence}");
+    }
+    let word = escape_html(&entry.word);
+    let definition = escape_html(&entry.definition);
+    match note_type {
+        NoteType::DaJa | NoteType::JaDa => {
+            let pos = entry.pos.iter().map(|&pos| pos_label(pos)).join(", ");
+            let pronunciation = entry.pronunciations.iter().join(", ");
+            let forms = (entry.other_forms.iter())
+                .chain(&entry.other_adjective_forms)
+                .map(form_cell)
+                .join(", ");
+            let fields = vec![
+                word,
+                pos,
+                escape_html(&pronunciation),
+                escape_html(&forms),
+                definition,
+            ];
+            vec![(guid(&key), fields)]
+        }
+        NoteType::Inflection => {
+            if !matches!(entry.pos.first(), Some(Pos::Verb | Pos::Noun(_))) {
+                return vec![];
+            }
+            slots(entry)
+                .into_iter()
+                .filter_map(|(form, slot)| Some((slot?.label(), form)))
+                .map(|(slot, form)| {
+                    let fields = vec![
+                        word.clone(),
+                        definition.clone(),
+                        slot.to_string(),
+                        escape_html(&form_cell(form)),
+                        escape_html(&form.pronunciations.iter().join(", ")),
+                    ];
+                    // A slot can hold several forms, e.g. two plurals
+                    let guid = guid(&format!("{key}\u{1f}{slot}\u{1f}{}", form.word));
+                    (guid, fields)
+                })
+                .collect()
+        }
+    }
+}
+
+fn guid(key: &str) -> String {
+    sha1_smol::Sha1::from(key).digest().to_string()[..20].to_string()
+}
+
+/// Adds a note with its card.  `position` orders the new cards, starting from 1.
+#[allow(clippy::too_many_arguments)]
+fn add_note(
+    tx: &Transaction,
+    note_type: NoteType,
+    deck_id: i64,
+    guid: &str,
+    fields: &[String],
+    id: i64,
+    position: i64,
+    now_s: i64,
+) -> anyhow::Result<()> {
+    let sort_field = &fields[0];
+    // The first 8 hex digits of the SHA-1 of the sort field, used by Anki to find duplicates
+    let checksum = i64::from_str_radix(
+        &sha1_smol::Sha1::from(sort_field).digest().to_string()[..8],
+        16,
+    )?;
+    tx.prepare_cached("INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, '', ?5, ?6, ?7, 0, '')")?
+        .execute(params![
+            id,
+            guid,
+            note_type.model_id(),
+            now_s,
+            fields.join("\u{1f}"),
+            sort_field,
+            checksum
+        ])?;
+    tx.prepare_cached(
+        "INSERT INTO cards VALUES (?1, ?1, ?2, 0, ?3, -1, 0, 0, ?4, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+    )?
+    .execute(params![id, deck_id, now_s, position])?;
+    Ok(())
+}
+
+fn collection_conf(deck_id: i64) -> Value {
+    json!({
+        "activeDecks": [deck_id],
+        "curDeck": deck_id,
+        "newSpread": 0,
+        "collapseTime": 1200,
+        "timeLim": 0,
+        "estTimes": true,
+        "dueCounts": true,
+        "curModel": null,
+        "nextPos": 1,
+        "sortType": "noteFld",
+        "sortBackwards": false,
+        "addToCur": true,
+    })
+}
+
+fn models(note_types: &[NoteType], deck_id: i64, now_s: i64) -> Value {
+    let models = note_types.iter().map(|&note_type| {
+        let (front, back) = note_type.templates();
+        let fields = (note_type.fields().iter().enumerate())
+            .map(|(ord, name)| {
+                json!({
+                    "name": name,
+                    "ord": ord,
+                    "sticky": false,
+                    "rtl": false,
+                    "font": "Arial",
+                    "size": 20,
+                    "media": [],
+                })
+            })
+            .collect_vec();
+        let model = json!({
+            "id": note_type.model_id(),
+            "name": note_type.name(),
+            "type": 0,
+            "mod": now_s,
+            "usn": -1,
+            "sortf": 0,
+            "did": deck_id,
+            "tmpls": [{
+                "name": "Card 1",
+                "ord": 0,
+                "qfmt": front,
+                "afmt": back,
+                "did": null,
+                "bqfmt": "",
+                "bafmt": "",
+            }],
+            "flds": fields,
+            "css": CSS,
+            "latexPre": "",
+            "latexPost": "",
+            "tags": [],
+            "vers": [],
+            "req": [[0, "any", [0]]],
+        });
+        (note_type.model_id().to_string(), model)
+    });
+    Value::Object(models.collect())
+}
+
+fn decks(deck_id: i64, deck_name: &str, now_s: i64) -> Value {
+    let deck = |id: i64, name: &str| {
+        json!({
+            "id": id,
+            "name": name,
+            "desc": "",
+            "mod": now_s,
+            "usn": -1,
+            "dyn": 0,
+            "conf": 1,
+            "collapsed": false,
+            "extendNew": 10,
+            "extendRev": 50,
+            "newToday": [0, 0],
+            "revToday": [0, 0],
+            "lrnToday": [0, 0],
+            "timeToday": [0, 0],
+        })
+    };
+    let decks = [
+        ("1".to_string(), deck(1, "Default")),
+        (deck_id.to_string(), deck(deck_id, deck_name)),
+    ];
+    Value::Object(decks.into_iter().collect())
+}
+
+fn deck_conf() -> Value {
+    json!({
+        "1": {
+            "id": 1,
+            "name": "Default",
+            "mod": 0,
+            "usn": 0,
+            "maxTaken": 60,
+            "autoplay": true,
+            "timer": 0,
+            "replayq": true,
+            "dyn": false,
+            "new": {
+                "delays": [1, 10],
+                "ints": [1, 4, 7],
+                "initialFactor": 2500,
+                "order": 1,
+                "perDay": 20,
+                "bury": true,
+            },
+            "rev": {
+                "perDay": 200,
+                "ease4": 1.3,
+                "maxIvl": 36500,
+                "hardFactor": 1.2,
+                "bury": true,
+            },
+            "lapse": {
+                "delays": [10],
+                "mult": 0,
+                "minInt": 1,
+                "leechFails": 8,
+                "leechAction": 0,
+            },
+        }
+    })
+}
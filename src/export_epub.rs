@@ -0,0 +1,257 @@
+use std::{
+    io::Write,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use fs_err::File;
+use itertools::Itertools;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    output::{escape_html, pos_label},
+    parse_dictionary::EntryBuf,
+};
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+const CSS: &str = ".pos { font-style: italic; }
+.ipa { color: #555; }
+";
+
+/// A letter heading and the XHTML file of its entries.
+struct Section {
+    title: String,
+    file_name: String,
+}
+
+/// Writes an EPUB 3 dictionary with one XHTML file per letter heading.  Every entry is an
+/// `<idx:entry>` whose `<idx:infl>` lists the inflected forms, so Kindle lookup finds the entry
+/// from any of them.
+pub fn export_epub(
+    path: &Path,
+    entries: impl Iterator<Item = anyhow::Result<EntryBuf>>,
+) -> anyhow::Result<()> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    // The mimetype has to be the first file and must not be compressed
+    zip.start_file(
+        "mimetype",
+        FileOptions::default().compression_method(CompressionMethod::Stored),
+    )?;
+    zip.write_all(b"application/epub+zip")?;
+    zip.start_file("META-INF/container.xml", FileOptions::default())?;
+    zip.write_all(CONTAINER.as_bytes())?;
+    zip.start_file("OEBPS/style.css", FileOptions::default())?;
+    zip.write_all(CSS.as_bytes())?;
+
+    let title = (path.file_stem()).map_or("Danish dictionary".into(), |s| s.to_string_lossy());
+    let mut sections: Vec<Section> = vec![];
+    let mut current: Option<(Option<String>, String)> = None;
+    for entry in entries {
+        let entry = entry?;
+        let section = entry.section.as_deref().map(str::to_owned);
+        if current.as_ref().map(|(s, _)| s) != Some(&section) {
+            if let Some((section, body)) = current.take() {
+                sections.push(write_section(&mut zip, sections.len(), section, &body)?);
+            }
+            current = Some((section, String::new()));
+        }
+        let (_, body) = current.as_mut().expect("Set above");
+        body.push_str(&render_entry(&entry));
+    }
+    if let Some((section, body)) = current.take() {
+        sections.push(write_section(&mut zip, sections.len(), section, &body)?);
+    }
+
+    zip.start_file("OEBPS/nav.xhtml", FileOptions::default())?;
+    zip.write_all(navigation(&title, &sections).as_bytes())?;
+    zip.start_file("OEBPS/content.opf", FileOptions::default())?;
+    zip.write_all(package(&title, &sections)?.as_bytes())?;
+    zip.finish()?;
+    Ok(())
+}
+
+fn write_section(
+    zip: &mut ZipWriter<File>,
+    index: usize,
+    title: Option<String>,
+    body: &str,
+) -> anyhow::Result<Section> {
+    // Entries before the first heading, e.g. when extraction starts in the middle of a letter
+    let title = title.unwrap_or_else(|| "—".into());
+    let file_name = format!("section_{:03}.xhtml", index + 1);
+    zip.start_file(format!("OEBPS/{file_name}"), FileOptions::default())?;
+    write!(
+        zip,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops"
+  xmlns:idx="https://kindlegen.s3.amazonaws.com/AmazonKindlePublishingGuidelines.pdf"
+  xmlns:mbp="https://kindlegen.s3.amazonaws.com/AmazonKindlePublishingGuidelines.pdf"
+  xml:lang="da" lang="da">
+<head>
+  <meta charset="UTF-8"/>
+  <title>{title}</title>
+  <link rel="stylesheet" type="text/css" href="style.css"/>
+</head>
+<body>
+<mbp:frameset>
+<h1>{title}</h1>
+{body}</mbp:frameset>
+</body>
+</html>
+"#,
+        title = escape_html(&title),
+    )?;
+    Ok(Section { title, file_name })
+}
+
+fn render_entry(entry: &EntryBuf) -> String {
+    let word = escape_html(&entry.word);
+    let mut html = format!(
+        "<idx:entry name=\"default\" scriptable=\"yes\" spell=\"yes\">\n\
+         <idx:orth value=\"{word}\"><b>{word}</b>"
+    );
+    if let Some(homograph) = entry.homograph {
+        html += &format!("<sup>{homograph}</sup>");
+    }
+    let forms = entry.inflected_forms().collect_vec();
+    if !forms.is_empty() {
+        html += "\n<idx:infl>";
+        for form in forms {
+            html += &format!("<idx:iform value=\"{}\"/>", escape_html(form));
+        }
+        html += "</idx:infl>\n";
+    }
+    html += "</idx:orth>";
+    if !entry.pos.is_empty() {
+        let pos = entry.pos.iter().map(|&pos| pos_label(pos)).join(", ");
+        html += &format!(" <span class=\"pos\">{pos}</span>");
+    }
+    if !entry.pronunciations.is_empty() {
+        let ipa = entry
+            .pronunciations
+            .iter()
+            .map(|s| escape_html(s))
+            .join(", ");
+        html += &format!(" <span class=\"ipa\">[{ipa}]</span>");
+    }
+    let senses = entry.senses();
+    if senses.len() > 1 {
+        html += "\n<ol>";
+        for sense in senses {
+            html += &format!("<li>{}</li>", escape_html(sense));
+        }
+        html += "</ol>";
+    } else {
+        html += &format!("\n<p>{}</p>", escape_html(&entry.definition));
+    }
+    html += "\n</idx:entry>\n<hr/>\n";
+    html
+}
+
+fn navigation(title: &str, sections: &[Section]) -> String {
+    let items = sections
+        .iter()
+        .map(|s| {
+            format!(
+                "      <li><a href=\"{}\">{}</a></li>",
+                s.file_name,
+                escape_html(&s.title)
+            )
+        })
+        .join("\n");
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head>
+  <meta charset="UTF-8"/>
+  <title>{title}</title>
+</head>
+<body>
+  <nav epub:type="toc" id="toc">
+    <h1>{title}</h1>
+    <ol>
+{items}
+    </ol>
+  </nav>
+</body>
+</html>
+"#,
+        title = escape_html(title),
+    )
+}
+
+fn package(title: &str, sections: &[Section]) -> anyhow::Result<String> {
+    let modified = modified_timestamp()?;
+    let manifest = sections
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            format!(
+                "    <item id=\"section{i}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>",
+                s.file_name
+            )
+        })
+        .join("\n");
+    let spine = (0..sections.len())
+        .map(|i| format!("    <itemref idref=\"section{i}\"/>"))
+        .join("\n");
+    Ok(format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="uid" xml:lang="da">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="uid">urn:danish-dictionary-parser:{identifier}</dc:identifier>
+    <dc:title>{title}</dc:title>
+    <dc:language>da</dc:language>
+    <dc:language>ja</dc:language>
+    <dc:type>dictionary</dc:type>
+    <meta property="dcterms:modified">{modified}</meta>
+    <meta property="source-language">da</meta>
+    <meta property="target-language">ja</meta>
+    <meta name="DictionaryInLanguage" content="da"/>
+    <meta name="DictionaryOutLanguage" content="ja"/>
+    <meta name="DefaultLookupIndex" content="default"/>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="css" href="style.css" media-type="text/css"/>
+{manifest}
+  </manifest>
+  <spine>
+{spine}
+  </spine>
+</package>
+"#,
+        identifier = escape_html(&title.to_lowercase().replace(' ', "-")),
+        title = escape_html(title),
+    ))
+}
+
+/// `dcterms:modified` in `CCYY-MM-DDThh:mm:ssZ`, without pulling in a date crate.
+fn modified_timestamp() -> anyhow::Result<String> {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let days = i64::try_from(secs / 86400)?;
+    let time = secs % 86400;
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    Ok(format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    ))
+}
@@ -0,0 +1,84 @@
+use serde_json::{json, Value};
+
+use crate::{
+    inflection::slots,
+    parse_dictionary::{EntryBuf, Pos},
+};
+
+/// The records of `entry` in the wiktextract schema used by kaikki.org, one per part of speech as
+/// Wiktionary has a section per part of speech.  Entries that only point at another entry become
+/// redirects.
+pub fn kaikki_records(entry: &EntryBuf) -> Vec<Value> {
+    if entry.pos.is_empty() && entry.other_forms.is_empty() {
+        if let [xr] = &entry.see_also[..] {
+            return vec![json!({"title": entry.word, "redirect": xr.word})];
+        }
+    }
+
+    let sounds = (entry.pronunciations.iter())
+        .map(|ipa| json!({ "ipa": format!("[{ipa}]") }))
+        .collect::<Vec<_>>();
+    let mut forms = vec![];
+    for (form, slot) in slots(entry) {
+        let tags = slot.map_or(&[][..], |slot| slot.tags());
+        for form in std::iter::once(form).chain(&form.slashed) {
+            forms.push(json!({"form": form.word, "tags": tags}));
+        }
+    }
+    let senses = (entry.senses().into_iter())
+        .map(|sense| json!({ "glosses": [sense] }))
+        .collect::<Vec<_>>();
+    let related = (entry.see_also.iter())
+        .map(|xr| json!({ "word": xr.word }))
+        .collect::<Vec<_>>();
+
+    let pos_names = if entry.pos.is_empty() {
+        vec!["unknown"]
+    } else {
+        entry.pos.iter().map(|&pos| pos_name(pos)).collect()
+    };
+    pos_names
+        .into_iter()
+        .map(|pos| {
+            let mut record = json!({
+                "word": entry.word,
+                "lang": "Danish",
+                "lang_code": "da",
+                "pos": pos,
+                "senses": senses,
+            });
+            let object = record.as_object_mut().expect("Created as an object");
+            if let Some(homograph) = entry.homograph {
+                object.insert("etymology_number".into(), homograph.into());
+            }
+            for (key, value) in [
+                ("sounds", &sounds),
+                ("forms", &forms),
+                ("related", &related),
+            ] {
+                if !value.is_empty() {
+                    object.insert(key.into(), value.clone().into());
+                }
+            }
+            record
+        })
+        .collect()
+}
+
+/// wiktextract's names for the parts of speech.
+fn pos_name(pos: Pos) -> &'static str {
+    match pos {
+        Pos::Noun(_) => "noun",
+        Pos::ProperNoun => "name",
+        Pos::Pronoun | Pos::FormalSubject => "pron",
+        Pos::Numeral => "num",
+        Pos::Adjective(_) => "adj",
+        Pos::Verb => "verb",
+        Pos::Adverb => "adv",
+        Pos::Preposition => "prep",
+        Pos::Conjunction => "conj",
+        Pos::Interjection => "intj",
+        Pos::InfinitiveMarker => "particle",
+        Pos::Article | Pos::IndefiniteArticle => "article",
+    }
+}
@@ -0,0 +1,160 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection, Transaction};
+
+use crate::{
+    output::pos_label,
+    parse_dictionary::{EntryBuf, OtherForm},
+};
+
+const SCHEMA: &str = "
+    CREATE TABLE entries (
+        id INTEGER PRIMARY KEY,
+        word TEXT NOT NULL,
+        definition TEXT NOT NULL
+    );
+    CREATE TABLE parts_of_speech (
+        entry_id INTEGER NOT NULL REFERENCES entries(id),
+        position INTEGER NOT NULL,
+        pos TEXT NOT NULL
+    );
+    -- `kind` is 'other' for `other_forms` and 'adjective' for `other_adjective_forms`.
+    -- Slashed alternatives refer to the form they are an alternative of.
+    CREATE TABLE forms (
+        id INTEGER PRIMARY KEY,
+        entry_id INTEGER NOT NULL REFERENCES entries(id),
+        kind TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        alternative_of INTEGER REFERENCES forms(id),
+        form TEXT NOT NULL
+    );
+    -- `form_id` is NULL for the pronunciations of the headword.
+    CREATE TABLE pronunciations (
+        entry_id INTEGER NOT NULL REFERENCES entries(id),
+        form_id INTEGER REFERENCES forms(id),
+        position INTEGER NOT NULL,
+        ipa TEXT NOT NULL
+    );
+    CREATE TABLE senses (
+        entry_id INTEGER NOT NULL REFERENCES entries(id),
+        position INTEGER NOT NULL,
+        definition TEXT NOT NULL
+    );
+    CREATE INDEX entries_word ON entries(word COLLATE NOCASE);
+    CREATE INDEX forms_form ON forms(form COLLATE NOCASE);
+    CREATE INDEX forms_entry ON forms(entry_id);
+    CREATE INDEX parts_of_speech_entry ON parts_of_speech(entry_id);
+    CREATE INDEX pronunciations_entry ON pronunciations(entry_id);
+    CREATE INDEX senses_entry ON senses(entry_id);
+    -- Every spelling that leads to an entry, headwords included.
+    CREATE VIEW lemmas (form, entry_id, kind) AS
+        SELECT word, id, 'headword' FROM entries
+        UNION ALL
+        SELECT form, entry_id, kind FROM forms;
+    CREATE VIRTUAL TABLE search USING fts5(
+        headword,
+        forms,
+        tokenize='unicode61 remove_diacritics 0'
+    );
+    -- unicode61 doesn't split Japanese into words, so the definitions are indexed by trigrams
+    -- and any substring of three or more characters can be searched for.  Shorter queries need
+    -- `definition LIKE '%…%'`.  The rowid is the entry id, as in `search`.
+    CREATE VIRTUAL TABLE search_definitions USING fts5(
+        definition,
+        tokenize='trigram'
+    );
+";
+
+/// Writes the entries to a new SQLite database at `path`, replacing any existing file.
+pub fn export_sqlite(
+    path: &Path,
+    entries: impl Iterator<Item = anyhow::Result<EntryBuf>>,
+) -> anyhow::Result<()> {
+    if path.exists() {
+        fs_err::remove_file(path)?;
+    }
+    let mut conn = Connection::open(path)?;
+    conn.execute_batch(SCHEMA)?;
+    let tx = conn.transaction()?;
+    for entry in entries {
+        insert_entry(&tx, &entry?)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+fn insert_entry(tx: &Transaction, entry: &EntryBuf) -> anyhow::Result<()> {
+    tx.prepare_cached("INSERT INTO entries (word, definition) VALUES (?1, ?2)")?
+        .execute(params![entry.word, entry.definition])?;
+    let entry_id = tx.last_insert_rowid();
+
+    let mut insert_pos = tx.prepare_cached(
+        "INSERT INTO parts_of_speech (entry_id, position, pos) VALUES (?1, ?2, ?3)",
+    )?;
+    for (i, &pos) in entry.pos.iter().enumerate() {
+        insert_pos.execute(params![entry_id, i, pos_label(pos)])?;
+    }
+    insert_pronunciations(tx, entry_id, None, &entry.pronunciations)?;
+
+    let mut all_forms = vec![];
+    for (kind, forms) in [
+        ("other", &entry.other_forms),
+        ("adjective", &entry.other_adjective_forms),
+    ] {
+        for (i, form) in forms.iter().enumerate() {
+            let form_id = insert_form(tx, entry_id, kind, i, None, form)?;
+            all_forms.push(&*form.word);
+            for alternative in &form.slashed {
+                insert_form(tx, entry_id, kind, i, Some(form_id), alternative)?;
+                all_forms.push(&*alternative.word);
+            }
+        }
+    }
+
+    let senses = entry.senses();
+    let mut insert_sense = tx.prepare_cached(
+        "INSERT INTO senses (entry_id, position, definition) VALUES (?1, ?2, ?3)",
+    )?;
+    for (i, sense) in senses.iter().enumerate() {
+        insert_sense.execute(params![entry_id, i, sense])?;
+    }
+
+    tx.prepare_cached("INSERT INTO search (rowid, headword, forms) VALUES (?1, ?2, ?3)")?
+        .execute(params![entry_id, entry.word, all_forms.join(" ")])?;
+    tx.prepare_cached("INSERT INTO search_definitions (rowid, definition) VALUES (?1, ?2)")?
+        .execute(params![entry_id, entry.definition])?;
+    Ok(())
+}
+
+fn insert_form(
+    tx: &Transaction,
+    entry_id: i64,
+    kind: &str,
+    position: usize,
+    alternative_of: Option<i64>,
+    form: &OtherForm,
+) -> anyhow::Result<i64> {
+    tx.prepare_cached(
+        "INSERT INTO forms (entry_id, kind, position, alternative_of, form)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?
+    .execute(params![entry_id, kind, position, alternative_of, form.word])?;
+    let form_id = tx.last_insert_rowid();
+    insert_pronunciations(tx, entry_id, Some(form_id), &form.pronunciations)?;
+    Ok(form_id)
+}
+
+fn insert_pronunciations(
+    tx: &Transaction,
+    entry_id: i64,
+    form_id: Option<i64>,
+    pronunciations: &[impl AsRef<str>],
+) -> anyhow::Result<()> {
+    let mut insert = tx.prepare_cached(
+        "INSERT INTO pronunciations (entry_id, form_id, position, ipa) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for (i, ipa) in pronunciations.iter().enumerate() {
+        insert.execute(params![entry_id, form_id, i, ipa.as_ref()])?;
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use std::{
+    cmp::Ordering,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use fs_err::File;
+use itertools::Itertools;
+
+use crate::{
+    output::{escape_html, pos_label},
+    parse_dictionary::EntryBuf,
+};
+
+/// An `.idx` record: the headword and the location of its article in the `.dict` file.
+struct Article {
+    word: String,
+    offset: u32,
+    size: u32,
+}
+
+/// Writes `<path>.ifo`, `<path>.idx`, `<path>.dict` and `<path>.syn`.  The articles are HTML
+/// (`sametypesequence=h`); every inflected form is a synonym of its headword.
+///
+/// The `.dict` file is left uncompressed, StarDict readers accept it as well as `.dict.dz`.
+pub fn export_stardict(
+    path: &Path,
+    entries: impl Iterator<Item = anyhow::Result<EntryBuf>>,
+) -> anyhow::Result<()> {
+    let mut dict = BufWriter::new(File::create(path.with_extension("dict"))?);
+    let mut articles = vec![];
+    // (form, index into `articles` before sorting)
+    let mut synonyms = vec![];
+    let mut offset = 0u32;
+    for entry in entries {
+        let entry = entry?;
+        let article = render_article(&entry);
+        dict.write_all(article.as_bytes())?;
+        let size = u32::try_from(article.len())?;
+        articles.push(Article {
+            word: entry.word.to_string(),
+            offset,
+            size,
+        });
+        offset = (offset.checked_add(size))
+            .ok_or_else(|| anyhow::anyhow!("The .dict file exceeds 4 GiB"))?;
+
+        for form in entry.inflected_forms() {
+            synonyms.push((form.to_string(), articles.len() - 1));
+        }
+    }
+    dict.flush()?;
+
+    // Readers binary search the index, so it has to be in StarDict's order.  The synonyms point
+    // at positions in the sorted index.
+    let mut order = (0..articles.len()).collect_vec();
+    order.sort_by(|&a, &b| stardict_cmp(&articles[a].word, &articles[b].word));
+    let mut sorted_position = vec![0; articles.len()];
+    for (position, &i) in order.iter().enumerate() {
+        sorted_position[i] = position;
+    }
+
+    let mut idx = vec![];
+    for &i in &order {
+        let article = &articles[i];
+        idx.extend_from_slice(article.word.as_bytes());
+        idx.push(0);
+        idx.extend_from_slice(&article.offset.to_be_bytes());
+        idx.extend_from_slice(&article.size.to_be_bytes());
+    }
+    fs_err::write(path.with_extension("idx"), &idx)?;
+
+    synonyms.sort_by(|(a, _), (b, _)| stardict_cmp(a, b));
+    let mut syn = BufWriter::new(File::create(path.with_extension("syn"))?);
+    for (form, i) in &synonyms {
+        syn.write_all(form.as_bytes())?;
+        syn.write_all(&[0])?;
+        syn.write_all(&u32::try_from(sorted_position[*i])?.to_be_bytes())?;
+    }
+    syn.flush()?;
+
+    let bookname =
+        (path.file_stem()).map_or("Danish dictionary".into(), |stem| stem.to_string_lossy());
+    let ifo = format!(
+        "StarDict's dict ifo file\n\
+         version=3.0.0\n\
+         bookname={bookname}\n\
+         wordcount={}\n\
+         synwordcount={}\n\
+         idxfilesize={}\n\
+         sametypesequence=h\n",
+        articles.len(),
+        synonyms.len(),
+        idx.len(),
+    );
+    fs_err::write(path.with_extension("ifo"), ifo)?;
+    Ok(())
+}
+
+/// The order of StarDict's index: ASCII case-insensitive, ties broken by the raw bytes.
+fn stardict_cmp(a: &str, b: &str) -> Ordering {
+    let fold = |s: &str| s.bytes().map(|b| b.to_ascii_lowercase()).collect_vec();
+    fold(a).cmp(&fold(b)).then_with(|| a.cmp(b))
+}
+
+fn render_article(entry: &EntryBuf) -> String {
+    let mut html = format!("<b>{}</b>", escape_html(&entry.word));
+    if !entry.pos.is_empty() {
+        let pos = entry.pos.iter().map(|&pos| pos_label(pos)).join(", ");
+        html += &format!(" <i>{pos}</i>");
+    }
+    if !entry.pronunciations.is_empty() {
+        let ipa = entry
+            .pronunciations
+            .iter()
+            .map(|s| escape_html(s))
+            .join(", ");
+        html += &format!(" [{ipa}]");
+    }
+    html += &format!("<br>{}", escape_html(&entry.definition));
+    html
+}
@@ -0,0 +1,192 @@
+use std::{
+    collections::HashMap,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use fs_err::File;
+
+use crate::{
+    output::{escape_html as escape_xml, pos_label, unique_ids},
+    parse_dictionary::{EntryBuf, OtherForm},
+};
+
+const HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TEI xmlns="http://www.tei-c.org/ns/1.0" xml:lang="ja">
+  <teiHeader>
+    <fileDesc>
+      <titleStmt>
+        <title>Danish–Japanese dictionary</title>
+      </titleStmt>
+      <publicationStmt>
+        <p>Converted from the PDF edition by danish-dictionary-parser.</p>
+      </publicationStmt>
+      <sourceDesc>
+        <p>Danish–Japanese dictionary (PDF).</p>
+      </sourceDesc>
+    </fileDesc>
+  </teiHeader>
+  <text>
+    <body>
+"#;
+
+const FOOTER: &str = "    </body>
+  </text>
+</TEI>
+";
+
+/// Writes the entries as a TEI Lex-0 document.  The entries are collected first, as a
+/// cross-reference can point at an entry further down.
+pub fn export_tei(
+    path: &Path,
+    entries: impl Iterator<Item = anyhow::Result<EntryBuf>>,
+) -> anyhow::Result<()> {
+    let entries: Vec<EntryBuf> = entries.collect::<anyhow::Result<_>>()?;
+    let ids = entry_ids(&entries);
+    let targets = Targets::new(&entries, &ids);
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(HEADER.as_bytes())?;
+    for (entry, id) in entries.iter().zip(&ids) {
+        write_entry(&mut writer, entry, id, &targets)?;
+    }
+    writer.write_all(FOOTER.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// The `xml:id` of each entry.  Headwords without a homograph number may repeat, the later ones
+/// get a suffix.
+fn entry_ids(entries: &[EntryBuf]) -> Vec<String> {
+    unique_ids(
+        (entries.iter())
+            .map(|entry| entry_id(&entry.word, entry.homograph))
+            .collect(),
+    )
+}
+
+/// Resolves cross-references to the ids that are written.
+struct Targets<'a> {
+    /// The homograph number and id of the entries with each headword, in document order.
+    by_word: HashMap<&'a str, Vec<(Option<u8>, &'a str)>>,
+}
+
+impl<'a> Targets<'a> {
+    fn new(entries: &'a [EntryBuf], ids: &'a [String]) -> Self {
+        let mut by_word: HashMap<_, Vec<_>> = HashMap::new();
+        for (entry, id) in entries.iter().zip(ids) {
+            (by_word.entry(entry.word.as_ref()).or_default()).push((entry.homograph, id.as_str()));
+        }
+        Targets { by_word }
+    }
+
+    /// A reference without a homograph number points at the first entry with the headword, as
+    /// does a reference to a homograph number that no entry has.  `None` if there's no entry
+    /// with the headword.
+    fn resolve(&self, word: &str, homograph: Option<u8>) -> Option<&'a str> {
+        let candidates = self.by_word.get(word)?;
+        let exact = candidates.iter().find(|(h, _)| *h == homograph);
+        Some(exact.unwrap_or(&candidates[0]).1)
+    }
+}
+
+fn write_entry(
+    w: &mut impl Write,
+    entry: &EntryBuf,
+    id: &str,
+    targets: &Targets,
+) -> anyhow::Result<()> {
+    write!(w, r#"      <entry xml:id="{id}" xml:lang="da""#)?;
+    if let Some(homograph) = entry.homograph {
+        write!(w, r#" n="{homograph}""#)?;
+    }
+    writeln!(w, ">")?;
+
+    writeln!(w, r#"        <form type="lemma">"#)?;
+    writeln!(w, "          <orth>{}</orth>", escape_xml(&entry.word))?;
+    for ipa in &entry.pronunciations {
+        writeln!(
+            w,
+            r#"          <pron notation="ipa">{}</pron>"#,
+            escape_xml(ipa)
+        )?;
+    }
+    writeln!(w, "        </form>")?;
+
+    if !entry.pos.is_empty() {
+        writeln!(w, "        <gramGrp>")?;
+        for &pos in &entry.pos {
+            writeln!(w, "          <pos>{}</pos>", pos_label(pos))?;
+        }
+        writeln!(w, "        </gramGrp>")?;
+    }
+
+    for form in (entry.other_forms.iter()).chain(&entry.other_adjective_forms) {
+        write_inflected_form(w, form)?;
+    }
+
+    for (n, sense) in (1..).zip(entry.senses()) {
+        writeln!(w, r#"        <sense xml:id="{id}.{n}" n="{n}">"#)?;
+        writeln!(
+            w,
+            r#"          <def xml:lang="ja">{}</def>"#,
+            escape_xml(sense)
+        )?;
+        writeln!(w, "        </sense>")?;
+    }
+
+    for xr in &entry.see_also {
+        writeln!(w, r#"        <xr type="related">"#)?;
+        match targets.resolve(&xr.word, xr.homograph) {
+            Some(target) => writeln!(
+                w,
+                r##"          <ref type="entry" target="#{target}">{}</ref>"##,
+                escape_xml(&xr.word)
+            )?,
+            // Not in the dictionary, or not parsed
+            None => writeln!(
+                w,
+                r#"          <ref type="entry">{}</ref>"#,
+                escape_xml(&xr.word)
+            )?,
+        }
+        writeln!(w, "        </xr>")?;
+    }
+    writeln!(w, "      </entry>")?;
+    Ok(())
+}
+
+/// A slashed alternative is another spelling of the same inflected form.
+fn write_inflected_form(w: &mut impl Write, form: &OtherForm) -> anyhow::Result<()> {
+    writeln!(w, r#"        <form type="inflected">"#)?;
+    for form in std::iter::once(form).chain(&form.slashed) {
+        writeln!(w, "          <orth>{}</orth>", escape_xml(&form.word))?;
+        for ipa in &form.pronunciations {
+            writeln!(
+                w,
+                r#"          <pron notation="ipa">{}</pron>"#,
+                escape_xml(ipa)
+            )?;
+        }
+    }
+    writeln!(w, "        </form>")?;
+    Ok(())
+}
+
+/// An `xml:id` (an NCName) for the headword, so that cross-references can point at it.
+fn entry_id(word: &str, homograph: Option<u8>) -> String {
+    let word: String = word
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match homograph {
+        Some(homograph) => format!("da.{word}.{homograph}"),
+        None => format!("da.{word}"),
+    }
+}
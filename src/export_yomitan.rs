@@ -0,0 +1,187 @@
+use std::{io::Write, path::Path};
+
+use fs_err::File;
+use itertools::Itertools;
+use serde_json::{json, Value};
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::{
+    output::{form_cell, pos_label},
+    parse_dictionary::{EntryBuf, NounCount, Pos},
+};
+
+/// Yomitan loads the banks into memory one at a time, the official dictionaries use this size.
+const TERMS_PER_BANK: usize = 10_000;
+
+/// Writes a Yomitan dictionary archive (format 3) to `path`.
+///
+/// Each entry becomes a term with a structured-content glossary.  Every inflected form becomes a
+/// term whose glossary points back at the headword, so Yomitan shows the entry when hovering over
+/// the inflected form.
+pub fn export_yomitan(
+    path: &Path,
+    entries: impl Iterator<Item = anyhow::Result<EntryBuf>>,
+) -> anyhow::Result<()> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = FileOptions::default();
+
+    let title = (path.file_stem()).map_or("Danish dictionary".into(), |s| s.to_string_lossy());
+    zip.start_file("index.json", options)?;
+    serde_json::to_writer_pretty(
+        &mut zip,
+        &json!({
+            "title": title,
+            "revision": env!("CARGO_PKG_VERSION"),
+            "format": 3,
+            "sequenced": true,
+            "sourceLanguage": "da",
+            "targetLanguage": "ja",
+        }),
+    )?;
+
+    zip.start_file("tag_bank_1.json", options)?;
+    serde_json::to_writer(&mut zip, &tag_bank())?;
+
+    let mut bank = vec![];
+    let mut bank_number = 1;
+    for (sequence, entry) in (1..).zip(entries) {
+        let entry = entry?;
+        bank.push(lemma_term(&entry, sequence));
+        bank.extend(inflected_terms(&entry, sequence));
+        if bank.len() >= TERMS_PER_BANK {
+            write_bank(&mut zip, bank_number, &bank)?;
+            bank.clear();
+            bank_number += 1;
+        }
+    }
+    if !bank.is_empty() {
+        write_bank(&mut zip, bank_number, &bank)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+fn write_bank(zip: &mut ZipWriter<File>, number: usize, bank: &[Value]) -> anyhow::Result<()> {
+    zip.start_file(format!("term_bank_{number}.json"), FileOptions::default())?;
+    serde_json::to_writer(&mut *zip, bank)?;
+    zip.flush()?;
+    Ok(())
+}
+
+/// `[expression, reading, definition tags, rules, score, glossary, sequence, term tags]`
+fn lemma_term(entry: &EntryBuf, sequence: i64) -> Value {
+    let tags = entry.pos.iter().map(|&pos| pos_tag(pos)).join(" ");
+    json!([
+        entry.word,
+        "",
+        tags,
+        "",
+        0,
+        [structured_content(entry)],
+        sequence,
+        "",
+    ])
+}
+
+/// One term per distinct inflected form, deinflecting to the headword.
+///
+/// The terms share the `sequence` of the headword's term.  With `"sequenced": true`, Yomitan's
+/// merged mode groups the terms that share a sequence into one result.  So the form is shown
+/// with the entry it belongs to, not as a separate result that only names the headword.  Each
+/// entry has its own sequence, so repeated headwords and homographs aren't merged.
+fn inflected_terms(entry: &EntryBuf, sequence: i64) -> Vec<Value> {
+    entry
+        .inflected_forms()
+        .map(|form| json!([form, "", "", "", -1, [[entry.word, []]], sequence, ""]))
+        .collect()
+}
+
+fn structured_content(entry: &EntryBuf) -> Value {
+    let mut content = vec![];
+    if !entry.pronunciations.is_empty() {
+        content.push(json!({
+            "tag": "div",
+            "data": {"content": "pronunciation"},
+            "content": format!("[{}]", entry.pronunciations.iter().join(", ")),
+        }));
+    }
+    let senses = entry.senses();
+    if senses.len() == 1 {
+        content.push(json!({
+            "tag": "div",
+            "data": {"content": "definition"},
+            "content": senses[0],
+        }));
+    } else if !senses.is_empty() {
+        content.push(json!({
+            "tag": "ol",
+            "data": {"content": "definition"},
+            "content": senses
+                .iter()
+                .map(|sense| json!({"tag": "li", "content": sense}))
+                .collect_vec(),
+        }));
+    }
+    let forms = (entry.other_forms.iter())
+        .chain(&entry.other_adjective_forms)
+        .map(form_cell)
+        .join(", ");
+    if !forms.is_empty() {
+        content.push(json!({
+            "tag": "div",
+            "data": {"content": "forms"},
+            "content": forms,
+        }));
+    }
+    json!({"type": "structured-content", "content": content})
+}
+
+const ALL_POS: [Pos; 17] = [
+    Pos::Noun(None),
+    Pos::Noun(Some(NounCount::Single)),
+    Pos::Noun(Some(NounCount::Multiple)),
+    Pos::ProperNoun,
+    Pos::Pronoun,
+    Pos::Numeral,
+    Pos::Adjective(false),
+    Pos::Adjective(true),
+    Pos::Verb,
+    Pos::Adverb,
+    Pos::Preposition,
+    Pos::Conjunction,
+    Pos::Interjection,
+    Pos::InfinitiveMarker,
+    Pos::Article,
+    Pos::IndefiniteArticle,
+    Pos::FormalSubject,
+];
+
+/// `[name, category, order, notes, score]`
+fn tag_bank() -> Vec<Value> {
+    ALL_POS
+        .iter()
+        .map(|&pos| json!([pos_tag(pos), "partOfSpeech", 0, pos_label(pos), 0]))
+        .collect()
+}
+
+fn pos_tag(pos: Pos) -> &'static str {
+    match pos {
+        Pos::Noun(None) => "n",
+        Pos::Noun(Some(NounCount::Single)) => "n-sg",
+        Pos::Noun(Some(NounCount::Multiple)) => "n-pl",
+        Pos::ProperNoun => "prop",
+        Pos::Pronoun => "pron",
+        Pos::Numeral => "num",
+        Pos::Adjective(false) => "adj",
+        Pos::Adjective(true) => "adj-inv",
+        Pos::Verb => "v",
+        Pos::Adverb => "adv",
+        Pos::Preposition => "prep",
+        Pos::Conjunction => "conj",
+        Pos::Interjection => "int",
+        Pos::InfinitiveMarker => "inf",
+        Pos::Article => "art",
+        Pos::IndefiniteArticle => "indef-art",
+        Pos::FormalSubject => "formal-subj",
+    }
+}
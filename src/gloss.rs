@@ -0,0 +1,99 @@
+use crate::parse_dictionary::Entry;
+
+const HALF_WIDTH_KATAKANA: &str = "ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン";
+/// Katakana whose voiced form is the next code point.
+const VOICEABLE: &str = "カキクケコサシスセソタチツテトハヒフヘホ";
+/// Katakana whose semi-voiced form is two code points on.
+const SEMI_VOICEABLE: &str = "ハヒフヘホ";
+
+/// Folds full-width ASCII to ASCII and half-width katakana to full-width, composing the
+/// half-width (semi-)voiced sound marks with the kana before them, so that a query matches
+/// regardless of how it was typed.
+pub fn normalize_width(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        let c = match c {
+            '\u{3000}' => ' ',
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).expect("ASCII"),
+            '｡' => '。',
+            '｢' => '「',
+            '｣' => '」',
+            '､' => '、',
+            '･' => '・',
+            '\u{FF66}'..='\u{FF9D}' => HALF_WIDTH_KATAKANA
+                .chars()
+                .nth((c as u32 - 0xFF66) as usize)
+                .expect("The table covers the range"),
+            'ﾞ' | 'ﾟ' => {
+                let voiced = c == 'ﾞ';
+                let composed = match out.chars().last() {
+                    Some('ウ') if voiced => Some('ヴ'),
+                    Some(p) if voiced && VOICEABLE.contains(p) => char::from_u32(p as u32 + 1),
+                    Some(p) if !voiced && SEMI_VOICEABLE.contains(p) => {
+                        char::from_u32(p as u32 + 2)
+                    }
+                    _ => None,
+                };
+                match composed {
+                    Some(composed) => {
+                        out.pop();
+                        composed
+                    }
+                    None if voiced => '゛',
+                    None => '゜',
+                }
+            }
+            c => c,
+        };
+        out.push(c);
+    }
+    out
+}
+
+/// A Japanese equivalent of a headword, as found in its definition.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Gloss {
+    /// Index of the sense in [`Entry::senses`].
+    pub sense: usize,
+    /// Position of the gloss within the sense.
+    pub position: usize,
+    /// The gloss after [`normalize_width`], without labels.
+    pub text: String,
+}
+
+/// Splits the senses of `entry` into the glosses separated by `，` and `；`, without the
+/// bracketed labels and explanations such as `［比喩］` and without cross-references.
+pub fn glosses(entry: &Entry) -> Vec<Gloss> {
+    let mut glosses = vec![];
+    for (sense, text) in entry.senses().into_iter().enumerate() {
+        let text = normalize_width(text);
+        let text = text.split('→').next().unwrap_or_default();
+        let text = strip_brackets(text);
+        let parts = text
+            .split([',', ';'])
+            .map(|s| s.trim().trim_end_matches(['．', '.', '。']).trim_end())
+            .filter(|s| !s.is_empty());
+        for (position, text) in parts.enumerate() {
+            glosses.push(Gloss {
+                sense,
+                position,
+                text: text.to_owned(),
+            });
+        }
+    }
+    glosses
+}
+
+fn strip_brackets(s: &str) -> String {
+    let mut depth = 0i32;
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '[' | '(' | '〔' | '【' => depth += 1,
+            ']' | ')' | '〕' | '】' => depth -= 1,
+            c if depth <= 0 => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
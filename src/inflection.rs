@@ -0,0 +1,152 @@
+use crate::parse_dictionary::{Entry, NounCount, OtherForm, Pos};
+
+/// The cell of a paradigm that an inflected form fills.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Slot {
+    DefiniteSingular,
+    IndefinitePlural,
+    DefinitePlural,
+    Present,
+    Past,
+    PastParticiple,
+    PresentParticiple,
+    Imperative,
+    Neuter,
+    /// The `-e` form of adjectives, used both for the plural and the definite.
+    PluralDefinite,
+    Comparative,
+    Superlative,
+    DefiniteSuperlative,
+}
+
+impl Slot {
+    /// Tags as used by wiktextract.
+    pub fn tags(self) -> &'static [&'static str] {
+        match self {
+            Slot::DefiniteSingular => &["definite", "singular"],
+            Slot::IndefinitePlural => &["indefinite", "plural"],
+            Slot::DefinitePlural => &["definite", "plural"],
+            Slot::Present => &["present"],
+            Slot::Past => &["past"],
+            Slot::PastParticiple => &["past", "participle"],
+            Slot::PresentParticiple => &["present", "participle"],
+            Slot::Imperative => &["imperative"],
+            Slot::Neuter => &["neuter", "singular"],
+            Slot::PluralDefinite => &["definite", "plural"],
+            Slot::Comparative => &["comparative"],
+            Slot::Superlative => &["superlative"],
+            Slot::DefiniteSuperlative => &["definite", "superlative"],
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Slot::DefiniteSingular => "definite singular",
+            Slot::IndefinitePlural => "indefinite plural",
+            Slot::DefinitePlural => "definite plural",
+            Slot::Present => "present",
+            Slot::Past => "past",
+            Slot::PastParticiple => "past participle",
+            Slot::PresentParticiple => "present participle",
+            Slot::Imperative => "imperative",
+            Slot::Neuter => "neuter",
+            Slot::PluralDefinite => "plural/definite",
+            Slot::Comparative => "comparative",
+            Slot::Superlative => "superlative",
+            Slot::DefiniteSuperlative => "definite superlative",
+        }
+    }
+}
+
+const NOUN: [Slot; 3] = [
+    Slot::DefiniteSingular,
+    Slot::IndefinitePlural,
+    Slot::DefinitePlural,
+];
+const ADJECTIVE: [Slot; 5] = [
+    Slot::Neuter,
+    Slot::PluralDefinite,
+    Slot::Comparative,
+    Slot::Superlative,
+    Slot::DefiniteSuperlative,
+];
+
+/// The slot of each of the `other_forms` and `other_adjective_forms` of the entry, in that
+/// order, or `None` where the form can't be placed.  Slashed alternatives share the slot of the
+/// form they follow.
+///
+/// The dictionary lists the forms in paradigm order, so the position decides where the number of
+/// forms matches the paradigm; otherwise the ending of the form does.
+pub fn slots<'e, 'a>(entry: &'e Entry<'a>) -> Vec<(&'e OtherForm<'a>, Option<Slot>)> {
+    let forms = &entry.other_forms;
+    let other_forms: Vec<Option<Slot>> = match entry.pos.first() {
+        Some(Pos::Noun(None)) if forms.len() == NOUN.len() => {
+            NOUN.iter().copied().map(Some).collect()
+        }
+        Some(Pos::Noun(count)) => forms
+            .iter()
+            .map(|form| noun_slot(&form.word, *count))
+            .collect(),
+        Some(Pos::Verb) => (forms.iter().enumerate())
+            .map(|(i, form)| verb_slot(i, forms.len(), &form.word))
+            .collect(),
+        Some(Pos::Adjective(_)) => (forms.iter().enumerate())
+            .map(|(i, form)| adjective_slot(i, forms.len(), &form.word))
+            .collect(),
+        _ => vec![None; forms.len()],
+    };
+    let adjective_forms = (entry.other_adjective_forms.iter()).map(|form| {
+        if form.word.starts_with("mere ") {
+            Some(Slot::Comparative)
+        } else if form.word.starts_with("mest ") {
+            Some(Slot::Superlative)
+        } else {
+            None
+        }
+    });
+    forms
+        .iter()
+        .zip(other_forms)
+        .chain(entry.other_adjective_forms.iter().zip(adjective_forms))
+        .collect()
+}
+
+fn noun_slot(word: &str, count: Option<NounCount>) -> Option<Slot> {
+    match count {
+        // The headword is already plural
+        Some(NounCount::Multiple) => Some(Slot::DefinitePlural),
+        _ if word.ends_with("ne") => Some(Slot::DefinitePlural),
+        _ if word.ends_with("en") || word.ends_with("et") => Some(Slot::DefiniteSingular),
+        Some(NounCount::Single) => Some(Slot::DefiniteSingular),
+        None if word.ends_with('e') || word.ends_with('r') => Some(Slot::IndefinitePlural),
+        None => None,
+    }
+}
+
+/// Present, past and past participle always come first; the present participle and the
+/// imperative are optional.
+fn verb_slot(i: usize, len: usize, word: &str) -> Option<Slot> {
+    match i {
+        _ if word.ends_with("ende") => Some(Slot::PresentParticiple),
+        0 if len >= 3 => Some(Slot::Present),
+        1 if len >= 3 => Some(Slot::Past),
+        2 if len >= 3 => Some(Slot::PastParticiple),
+        _ if i >= 3 => Some(Slot::Imperative),
+        _ => None,
+    }
+}
+
+/// The full paradigm has five forms; irregular adjectives such as `lille` lack the neuter.
+/// Adjectives that aren't compared have fewer forms, which are placed by their ending.
+fn adjective_slot(i: usize, len: usize, word: &str) -> Option<Slot> {
+    match len {
+        5 => Some(ADJECTIVE[i]),
+        4 => Some(ADJECTIVE[i + 1]),
+        _ if word.ends_with("ste") => Some(Slot::DefiniteSuperlative),
+        _ if word.ends_with("st") => Some(Slot::Superlative),
+        _ if word.ends_with("ere") => Some(Slot::Comparative),
+        _ if word.ends_with('t') => Some(Slot::Neuter),
+        _ if word.ends_with('e') => Some(Slot::PluralDefinite),
+        _ => None,
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+use pdf::object::{PageRc, Resolve};
+
+use crate::{
+    decode_pdf_string::{
+        decode_pdf_string, encoding_differences, has_hardcoded_code_map, make_unicode_map,
+        EncodingDifference, FontMap,
+    },
+    walk_text::each_text,
+};
+
+/// What is known about a font resource of a page and how well its codes decode.
+pub struct FontReport {
+    /// Name of the font in the resources of the page, e.g. `F1`.
+    pub resource: String,
+    pub subtype: String,
+    pub base_font: Option<String>,
+    /// The tag that marks the font as a subset, e.g. `DXNKCI` in `DXNKCI+GaijiL`.
+    pub subset: Option<String>,
+    pub encoding: Option<String>,
+    pub differences: Vec<EncodingDifference>,
+    pub to_unicode: bool,
+    /// Whether [`has_hardcoded_code_map`] applies.
+    pub hardcoded: bool,
+    /// Number of mapped codes, or why no map could be made.
+    pub mapped: Result<usize, String>,
+    /// Number of codes drawn with the font on the page.
+    pub drawn: u64,
+    /// Number of times each unmapped code is drawn.
+    pub unmapped: BTreeMap<u32, u64>,
+}
+
+/// Inspects every font resource of `page`, including fonts that can't be decoded, and counts the
+/// codes that the content stream draws with each.
+pub fn inspect_fonts(
+    file: &pdf::file::File<Vec<u8>>,
+    page: &PageRc,
+) -> anyhow::Result<Vec<FontReport>> {
+    let resources = page.resources()?;
+    let mut reports = vec![];
+    let mut maps = vec![];
+    for (resource, &font) in resources.fonts().sorted_by_key(|(name, _)| *name) {
+        let font = file.get(font)?;
+        let base_font = font.name.as_ref().map(|name| name.as_str().to_owned());
+        let map = make_unicode_map(file, &font);
+        reports.push(FontReport {
+            resource: resource.to_owned(),
+            subtype: format!("{:?}", font.subtype),
+            subset: base_font.as_deref().and_then(subset_tag).map(str::to_owned),
+            hardcoded: base_font.as_deref().map_or(false, has_hardcoded_code_map),
+            base_font,
+            encoding: font.encoding().map(|e| format!("{:?}", e.base)),
+            differences: encoding_differences(&font),
+            to_unicode: font.to_unicode.is_some(),
+            mapped: map
+                .as_ref()
+                .map(|map| map.map.len())
+                .map_err(|e| format!("{e:#}")),
+            drawn: 0,
+            unmapped: BTreeMap::new(),
+        });
+        maps.push(map.ok());
+    }
+
+    if page.contents.is_none() {
+        return Ok(reports);
+    }
+    for entry in each_text(file, page)? {
+        let entry = entry?;
+        let i = match reports
+            .iter()
+            .position(|r| r.resource == entry.font.as_str())
+        {
+            Some(i) => i,
+            None => continue,
+        };
+        let report = &mut reports[i];
+        match &maps[i] {
+            Some(map) => count_codes(report, map, &entry.text),
+            // Without a map the codespace isn't known either, so count bytes
+            None => report.drawn += entry.text.as_bytes().len() as u64,
+        }
+    }
+    Ok(reports)
+}
+
+fn count_codes(report: &mut FontReport, map: &FontMap, text: &pdf::primitive::PdfString) {
+    for code in decode_pdf_string(map, text) {
+        report.drawn += 1;
+        if let Err(e) = code {
+            *report.unmapped.entry(e.code).or_default() += 1;
+        }
+    }
+}
+
+/// The six uppercase letters before the `+` of a subset font's name.
+fn subset_tag(base_font: &str) -> Option<&str> {
+    let (tag, _) = base_font.split_once('+')?;
+    (tag.len() == 6 && tag.bytes().all(|b| b.is_ascii_uppercase())).then_some(tag)
+}
+
+pub fn print_font_report(report: &FontReport) {
+    println!(
+        "  {}\t{}",
+        report.resource,
+        report.base_font.as_deref().unwrap_or("(no base font)")
+    );
+    println!("    subtype:     {}", report.subtype);
+    if let Some(subset) = &report.subset {
+        println!("    subset:      {subset}");
+    }
+    println!(
+        "    encoding:    {}, {} differences",
+        report.encoding.as_deref().unwrap_or("none"),
+        report.differences.len()
+    );
+    for difference in &report.differences {
+        let base = difference
+            .base
+            .as_ref()
+            .map_or("none".to_owned(), |base| format!("{base:?}"));
+        println!(
+            "      {:#04x}  {base} → {:?} (/{})",
+            difference.code, difference.text, difference.glyph_name
+        );
+    }
+    println!(
+        "    ToUnicode:   {}",
+        if report.to_unicode { "yes" } else { "no" }
+    );
+    println!(
+        "    hardcoded:   {}",
+        if report.hardcoded { "yes" } else { "no" }
+    );
+    match &report.mapped {
+        Ok(mapped) => println!("    mapped:      {mapped} codes"),
+        Err(e) => println!("    mapped:      none: {e}"),
+    }
+    let unmapped = report.unmapped.values().sum::<u64>();
+    print!(
+        "    drawn:       {} codes, {unmapped} unmapped",
+        report.drawn
+    );
+    if !report.unmapped.is_empty() {
+        let codes = (report.unmapped.iter())
+            .map(|(code, count)| format!("{code:#x}×{count}"))
+            .join(" ");
+        print!(" ({codes})");
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_subset_tag() {
+        assert_eq!(subset_tag("DXNKCI+GaijiL"), Some("DXNKCI"));
+        assert_eq!(
+            subset_tag("DXNKCI+Ipa-samdUclphon1SILDoulosL"),
+            Some("DXNKCI")
+        );
+        assert_eq!(subset_tag("Ryumin-Light"), None);
+        assert_eq!(subset_tag("DXNKC+GaijiL"), None);
+        assert_eq!(subset_tag("dxnkci+GaijiL"), None);
+        assert_eq!(subset_tag("DXNKC1+GaijiL"), None);
+    }
+}
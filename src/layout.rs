@@ -0,0 +1,268 @@
+use std::{ops::Range, thread};
+
+use anyhow::{anyhow, bail, Context};
+use itertools::Itertools;
+use pdf::object::PageRc;
+
+use crate::{
+    decode_pdf_string::{decode_pdf_string_lossy, DecodeStats, PageFonts},
+    walk_text::{each_text, TextEntry},
+};
+
+/// Coordinates that describe how the dictionary is laid out on a page.
+pub struct LayoutConfig {
+    /// Text at or below this y coordinate that precedes the body is the page footer.
+    pub footer_y: f32,
+    /// Minimum vertical distance between two lines.
+    pub line_gap: f32,
+    /// Lines drawn with a larger glyph size are letter headings.
+    pub heading_size: f32,
+    /// x coordinates of lines that start a new entry.
+    pub entry_x: Range<f32>,
+    /// x coordinates of lines that continue the previous entry.
+    pub continuation_x: Vec<Range<f32>>,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            footer_y: 50.0,
+            line_gap: 8.0,
+            heading_size: 11.0,
+            entry_x: 70.5..71.5,
+            continuation_x: vec![80.0..82.5, 91.5..92.5],
+        }
+    }
+}
+
+pub type ParsedTextEntry = (TextEntry, Vec<String>);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LineKind {
+    Heading,
+    Empty,
+    EntryStart,
+    Continuation,
+}
+
+pub struct Line {
+    pub kind: LineKind,
+    pub text: String,
+}
+
+impl LayoutConfig {
+    pub fn group_lines(
+        &self,
+        file: &pdf::file::File<Vec<u8>>,
+        page: &PageRc,
+    ) -> anyhow::Result<Vec<Vec<TextEntry>>> {
+        let mut last_y = f32::INFINITY;
+        let mut lines = vec![];
+        for entry in each_text(file, page)?.skip_while(|e| {
+            e.as_ref()
+                .map_or(false, |e| e.positions.coordinates().y <= self.footer_y)
+        }) {
+            let entry = entry?;
+            let p = entry.positions.coordinates();
+            if p.y < last_y - self.line_gap {
+                last_y = p.y;
+                lines.push(vec![entry]);
+            } else {
+                lines
+                    .last_mut()
+                    .expect("The branch above should run in the first iteration")
+                    .push(entry);
+            }
+        }
+        Ok(lines)
+    }
+
+    /// `line` must be non-empty.
+    pub fn classify(&self, line: &[ParsedTextEntry]) -> anyhow::Result<LineKind> {
+        Ok(if line[0].0.positions.glyph_size() > self.heading_size {
+            LineKind::Heading
+        } else if line.len() == 1 && line[0].1 == [" "] {
+            LineKind::Empty
+        } else if self.not_indented(&line[0].0)? {
+            LineKind::EntryStart
+        } else {
+            LineKind::Continuation
+        })
+    }
+
+    pub fn not_indented(&self, first_entry: &TextEntry) -> anyhow::Result<bool> {
+        Ok(match first_entry.positions.coordinates().x {
+            // Hack: manual indentation
+            _ if first_entry.text.as_bytes() == b" " => false,
+            x if self.entry_x.contains(&x) => true,
+            x if self.continuation_x.iter().any(|r| r.contains(&x)) => false,
+            x => bail!("Unexpected x coordinates: {x}"),
+        })
+    }
+
+    pub fn lines(&self, parsed_lines: Vec<Vec<ParsedTextEntry>>) -> anyhow::Result<Vec<Line>> {
+        parsed_lines
+            .into_iter()
+            .map(|line| {
+                let kind = self.classify(&line)?;
+                let text = line.into_iter().flat_map(|(_, chars)| chars).collect();
+                Ok(Line { kind, text })
+            })
+            .collect()
+    }
+}
+
+pub fn decode_lines(
+    fonts: &PageFonts,
+    lines: Vec<Vec<TextEntry>>,
+    stats: &mut DecodeStats,
+) -> anyhow::Result<Vec<Vec<ParsedTextEntry>>> {
+    let mut parsed_lines = vec![];
+    for line in lines {
+        let mut parsed_line = vec![];
+        for entry in line {
+            let (_, map) = fonts
+                .get(entry.font.as_str())
+                .with_context(|| format!("Font {:?} not found", entry.font))?;
+            let strings = decode_pdf_string_lossy(map, &entry.text, stats)
+                .into_iter()
+                .map(|e| e.to_owned())
+                .collect_vec();
+            parsed_line.push((entry, strings));
+        }
+        parsed_lines.push(parsed_line);
+    }
+    Ok(parsed_lines)
+}
+
+/// Splits the pages into `jobs` contiguous chunks and runs `extract` on each chunk on its own
+/// thread.  The results are returned in page order, so that joining the lines they contain into
+/// entries gives the same result as extracting the pages one after another.
+pub fn extract_in_chunks<T: Send>(
+    pages: &[u32],
+    jobs: usize,
+    extract: impl Fn(&[u32]) -> anyhow::Result<T> + Sync,
+) -> anyhow::Result<Vec<T>> {
+    let jobs = jobs.max(1);
+    let chunk_size = (pages.len() + jobs - 1) / jobs;
+    let extract = &extract;
+    thread::scope(|s| {
+        let handles = pages
+            .chunks(chunk_size.max(1))
+            .map(|chunk| s.spawn(move || extract(chunk)))
+            .collect_vec();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("Extraction thread panicked"))
+            .collect()
+    })
+}
+
+/// The text of an entry and the letter heading it appears under.
+pub struct Word {
+    pub text: String,
+    pub section: Option<String>,
+}
+
+/// Joins lines into the text of each entry, in the order the lines appear in the document.  An
+/// entry is yielded as soon as the line starting the next one arrives.
+pub struct Words<I> {
+    lines: I,
+    section: Option<String>,
+    pending: Option<Word>,
+}
+
+impl<I> Words<I> {
+    pub fn new(lines: I) -> Self {
+        Self {
+            lines,
+            section: None,
+            pending: None,
+        }
+    }
+}
+
+impl<I: Iterator<Item = anyhow::Result<Line>>> Iterator for Words<I> {
+    type Item = anyhow::Result<Word>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for line in &mut self.lines {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            match line.kind {
+                LineKind::Heading => self.section = Some(line.text.trim().to_owned()),
+                LineKind::Empty => {}
+                LineKind::EntryStart => {
+                    let word = Word {
+                        text: line.text,
+                        section: self.section.clone(),
+                    };
+                    if let Some(word) = self.pending.replace(word) {
+                        return Some(Ok(word));
+                    }
+                }
+                LineKind::Continuation => match self.pending.as_mut() {
+                    Some(word) => word.text.push_str(&line.text),
+                    None => return Some(Err(anyhow!("Found indented line before the first line"))),
+                },
+            }
+        }
+        self.pending.take().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lines of pages where entries continue across page breaks and headings start mid-page.
+    fn page_lines(page: u32) -> Vec<Line> {
+        let line = |kind, text: String| Line { kind, text };
+        let mut lines = vec![
+            line(LineKind::Continuation, format!(" fortsat {page}")),
+            line(LineKind::Empty, String::new()),
+        ];
+        if page % 3 == 0 {
+            lines.push(line(LineKind::Heading, format!(" {page} ")));
+        }
+        for i in 0..page % 4 {
+            lines.push(line(LineKind::EntryStart, format!("ord{page}.{i}: ")));
+            lines.push(line(LineKind::Continuation, "ø".repeat(i as usize)));
+        }
+        lines.push(line(LineKind::EntryStart, format!("sidst{page}")));
+        lines
+    }
+
+    fn words(lines: impl Iterator<Item = Line>) -> Vec<(String, Option<String>)> {
+        let mut lines = lines.peekable();
+        // The first page continues an entry from a page that isn't extracted
+        lines.next_if(|line| line.kind == LineKind::Continuation);
+        Words::new(lines.map(Ok))
+            .map(|word| word.map(|word| (word.text, word.section)))
+            .collect::<anyhow::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn chunked_extraction_joins_the_same_entries() {
+        let pages: Vec<u32> = (1..=23).collect();
+        let sequential = words(pages.iter().flat_map(|&page| page_lines(page)));
+        assert_eq!(sequential.len(), 59);
+        for jobs in 1..=pages.len() + 1 {
+            let chunks = extract_in_chunks(&pages, jobs, |chunk| {
+                Ok(chunk
+                    .iter()
+                    .flat_map(|&page| page_lines(page))
+                    .collect_vec())
+            })
+            .unwrap();
+            assert_eq!(
+                words(chunks.into_iter().flatten()),
+                sequential,
+                "{jobs} jobs"
+            );
+        }
+    }
+}
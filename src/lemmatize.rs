@@ -0,0 +1,57 @@
+use crate::{
+    inflection::{slots, Slot},
+    lookup::{Dictionary, MatchKind},
+    parse_dictionary::EntryBuf,
+};
+
+/// An entry that a form belongs to.
+#[derive(Clone, Copy, Debug)]
+pub struct EntryRef<'d> {
+    pub entry: &'d EntryBuf,
+    /// Whether the form is the headword itself rather than one of its inflected forms.
+    pub headword: bool,
+    /// The cell of the paradigm that the form fills, if it is inflected and could be placed.
+    pub slot: Option<Slot>,
+}
+
+/// The entries that `form` is the headword or an inflected form of, e.g. `knuse` for `knuste`.
+/// An ambiguous form gives every candidate, and a form that fills several cells of the same
+/// paradigm gives the entry once per cell.  Capitalized forms that aren't in the dictionary as
+/// such, e.g. at the start of a sentence, are looked up in lower case.
+pub fn lemmatize<'d>(dictionary: &'d Dictionary, form: &str) -> Vec<EntryRef<'d>> {
+    let candidates = lemmatize_exact(dictionary, form);
+    let lowercase = form.to_lowercase();
+    if candidates.is_empty() && lowercase != form {
+        return lemmatize_exact(dictionary, &lowercase);
+    }
+    candidates
+}
+
+fn lemmatize_exact<'d>(dictionary: &'d Dictionary, form: &str) -> Vec<EntryRef<'d>> {
+    let mut candidates = vec![];
+    for (entry, kind) in dictionary.with_form(form) {
+        if kind == MatchKind::Headword {
+            candidates.push(EntryRef {
+                entry,
+                headword: true,
+                slot: None,
+            });
+            continue;
+        }
+        let mut entry_slots = vec![];
+        for (other_form, slot) in slots(entry) {
+            let matches = std::iter::once(other_form)
+                .chain(&other_form.slashed)
+                .any(|f| f.word == form);
+            if matches && !entry_slots.contains(&slot) {
+                entry_slots.push(slot);
+            }
+        }
+        candidates.extend(entry_slots.into_iter().map(|slot| EntryRef {
+            entry,
+            headword: false,
+            slot,
+        }));
+    }
+    candidates
+}
@@ -1,5 +1,26 @@
+pub mod corrections;
 pub mod count_ops;
+pub mod debug_render;
 pub mod decode_pdf_string;
+pub mod explain_parse;
+pub mod export_anki;
+pub mod export_epub;
+pub mod export_kaikki;
+pub mod export_sqlite;
+pub mod export_stardict;
+pub mod export_tei;
+pub mod export_yomitan;
+pub mod gloss;
+pub mod inflection;
+pub mod inspect_fonts;
+pub mod layout;
+pub mod lemmatize;
+pub mod lookup;
+pub mod output;
 pub mod parse_dictionary;
+pub mod render_html;
+pub mod review;
+pub mod schema;
+pub mod serve;
 pub mod text_operator_parser;
 pub mod walk_text;
@@ -0,0 +1,412 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+};
+
+use itertools::Itertools;
+use serde::Serialize;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use crate::{
+    gloss::{glosses, normalize_width, Gloss},
+    inflection::Slot,
+    lemmatize::lemmatize,
+    output::{form_cell, pos_label},
+    parse_dictionary::EntryBuf,
+};
+
+/// How a query matched an entry, from the most to the least direct.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    Headword,
+    InflectedForm,
+    /// The query matched the headword after [`fold`].
+    FuzzyHeadword,
+    /// The query matched an inflected form after [`fold`].
+    FuzzyInflectedForm,
+}
+
+impl MatchKind {
+    fn fuzzy(self) -> Self {
+        match self {
+            MatchKind::Headword => MatchKind::FuzzyHeadword,
+            MatchKind::InflectedForm => MatchKind::FuzzyInflectedForm,
+            fuzzy => fuzzy,
+        }
+    }
+}
+
+/// How directly a Japanese query matched a gloss, from the most to the least direct.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GlossMatch {
+    Exact,
+    Prefix,
+    Contains,
+}
+
+/// Parsed entries indexed by headword and inflected form, and by gloss for the reverse lookup.
+pub struct Dictionary {
+    pub entries: Vec<EntryBuf>,
+    words: HashMap<String, Vec<(usize, MatchKind)>>,
+    folded: HashMap<String, Vec<(usize, MatchKind)>>,
+    /// The headwords after [`fold`] with the index of their entry, sorted for prefix search.
+    headwords: Vec<(String, usize)>,
+    /// The glosses of all entries with the index of their entry.
+    glosses: Vec<(usize, Gloss)>,
+    /// Indices into `glosses` by gloss text.
+    gloss_index: HashMap<String, Vec<usize>>,
+}
+
+impl Dictionary {
+    pub fn new(entries: Vec<EntryBuf>) -> Self {
+        let mut words = HashMap::<_, Vec<_>>::new();
+        let mut folded = HashMap::<_, Vec<_>>::new();
+        for (i, entry) in entries.iter().enumerate() {
+            let forms = std::iter::once((&*entry.word, MatchKind::Headword))
+                .chain((entry.inflected_forms()).map(|form| (form, MatchKind::InflectedForm)));
+            for (word, kind) in forms {
+                words.entry(word.to_owned()).or_default().push((i, kind));
+                folded
+                    .entry(fold(word))
+                    .or_default()
+                    .push((i, kind.fuzzy()));
+            }
+        }
+        let headwords = (entries.iter().enumerate())
+            .map(|(i, entry)| (fold(&entry.word), i))
+            .sorted()
+            .collect();
+        let glosses = (entries.iter().enumerate())
+            .flat_map(|(i, entry)| glosses(entry).into_iter().map(move |gloss| (i, gloss)))
+            .collect_vec();
+        let mut gloss_index = HashMap::<_, Vec<_>>::new();
+        for (g, (_, gloss)) in glosses.iter().enumerate() {
+            gloss_index.entry(gloss.text.clone()).or_default().push(g);
+        }
+        Dictionary {
+            entries,
+            words,
+            folded,
+            headwords,
+            glosses,
+            gloss_index,
+        }
+    }
+
+    /// The entries that `query` is the headword or an inflected form of, falling back to a match
+    /// that ignores case, accents and the spelling of `å`, `æ` and `ø`.  Each entry is returned
+    /// once with its most direct match, in dictionary order within each kind.
+    pub fn lookup(&self, query: &str) -> Vec<(&EntryBuf, MatchKind)> {
+        let query = query.trim();
+        let exact = self.words.get(query).into_iter().flatten();
+        let fuzzy = self.folded.get(&fold(query)).into_iter().flatten();
+        exact
+            .chain(fuzzy)
+            .sorted_by_key(|&&(i, kind)| (kind, i))
+            .unique_by(|&&(i, _)| i)
+            .map(|&(i, kind)| (&self.entries[i], kind))
+            .collect()
+    }
+
+    /// The entries that `word` is exactly the headword or an inflected form of, with how it
+    /// matched.  An entry comes once per way it matched.
+    pub fn with_form(&self, word: &str) -> impl Iterator<Item = (&EntryBuf, MatchKind)> {
+        (self.words.get(word).into_iter().flatten()).map(|&(i, kind)| (&self.entries[i], kind))
+    }
+
+    /// Up to `limit` entries whose headword starts with `prefix`, ignoring case, accents and the
+    /// spelling of `å`, `æ` and `ø`, ordered by their headword spelled that way.
+    pub fn with_prefix(&self, prefix: &str, limit: usize) -> Vec<&EntryBuf> {
+        let prefix = fold(prefix.trim());
+        let start = self.headwords.partition_point(|(word, _)| *word < prefix);
+        self.headwords[start..]
+            .iter()
+            .take_while(|(word, _)| word.starts_with(&prefix))
+            .take(limit)
+            .map(|&(_, i)| &self.entries[i])
+            .collect()
+    }
+
+    /// The entries with a gloss that is, starts with or contains the Japanese `query`.  They are
+    /// ranked by how directly the gloss matched, then by how early in the definition the gloss
+    /// comes, as the first glosses of the first sense are the closest equivalents.
+    pub fn reverse_lookup(&self, query: &str) -> Vec<(&EntryBuf, &Gloss, GlossMatch)> {
+        let query = normalize_width(query.trim());
+        if query.is_empty() {
+            return vec![];
+        }
+        let exact =
+            (self.gloss_index.get(&query).into_iter().flatten()).map(|&g| (g, GlossMatch::Exact));
+        let partial = (self.glosses.iter().enumerate()).filter_map(|(g, (_, gloss))| {
+            if gloss.text == query {
+                None
+            } else if gloss.text.starts_with(&query) {
+                Some((g, GlossMatch::Prefix))
+            } else if gloss.text.contains(&query) {
+                Some((g, GlossMatch::Contains))
+            } else {
+                None
+            }
+        });
+        exact
+            .chain(partial)
+            .map(|(g, kind)| (&self.glosses[g], kind))
+            .sorted_by_key(|((i, gloss), kind)| (*kind, gloss.sense, gloss.position, *i))
+            .unique_by(|((i, _), _)| *i)
+            .map(|((i, gloss), kind)| (&self.entries[*i], gloss, kind))
+            .collect()
+    }
+}
+
+/// Lowercases `word`, spells `å`, `æ` and `ø` as `aa`, `ae` and `oe` and removes accents, so
+/// that words typed without a Danish keyboard still match, e.g. `ide` for `idé`.  `å` is spelled
+/// out before the accents are removed, as decomposing it gives `a` and a ring.
+pub fn fold(word: &str) -> String {
+    word.nfc()
+        .collect::<String>()
+        .to_lowercase()
+        .replace('å', "aa")
+        .replace('æ', "ae")
+        .replace('ø', "oe")
+        .nfd()
+        .filter(|&c| !is_combining_mark(c))
+        .collect()
+}
+
+/// The entry as shown in the terminal: the headword with its homograph number, IPA and parts
+/// of speech, then the inflected forms and the numbered senses.
+pub fn format_entry(entry: &EntryBuf) -> String {
+    let mut header = entry.word.to_string();
+    if let Some(homograph) = entry.homograph {
+        header += &homograph.to_string();
+    }
+    if !entry.pronunciations.is_empty() {
+        header += &format!("  [{}]", entry.pronunciations.join(", "));
+    }
+    if !entry.pos.is_empty() {
+        header += &format!(
+            "  {}",
+            entry.pos.iter().map(|&pos| pos_label(pos)).join(", ")
+        );
+    }
+    let mut lines = vec![header];
+    let forms = (entry.other_forms.iter())
+        .chain(&entry.other_adjective_forms)
+        .map(form_cell)
+        .join(", ");
+    if !forms.is_empty() {
+        lines.push(format!("  {forms}"));
+    }
+    match &entry.senses()[..] {
+        [sense] => lines.push(format!("  {sense}")),
+        senses => {
+            for (i, sense) in senses.iter().enumerate() {
+                lines.push(format!("  {}. {sense}", i + 1));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// Prints the entries matching `query`, saying how they matched when it wasn't the headword.
+pub fn print_lookup(
+    dictionary: &Dictionary,
+    query: &str,
+    mut out: impl Write,
+) -> anyhow::Result<()> {
+    let matches = dictionary.lookup(query);
+    if matches.is_empty() {
+        writeln!(out, "No entries for {query:?}")?;
+    }
+    for (entry, kind) in matches {
+        match kind {
+            MatchKind::Headword => {}
+            MatchKind::InflectedForm => {
+                let slots = (lemmatize(dictionary, query.trim()).into_iter())
+                    .filter(|candidate| std::ptr::eq(candidate.entry, entry))
+                    .filter_map(|candidate| candidate.slot.map(Slot::label))
+                    .join(", ");
+                if slots.is_empty() {
+                    writeln!(out, "({query} is a form of {})", entry.word)?
+                } else {
+                    writeln!(out, "({query} is the {slots} of {})", entry.word)?
+                }
+            }
+            MatchKind::FuzzyHeadword | MatchKind::FuzzyInflectedForm => {
+                writeln!(out, "(closest match for {query})")?
+            }
+        }
+        writeln!(out, "{}\n", format_entry(entry))?;
+    }
+    Ok(())
+}
+
+/// Prints the headwords with a gloss matching the Japanese `query`, most direct first.
+pub fn print_reverse_lookup(
+    dictionary: &Dictionary,
+    query: &str,
+    mut out: impl Write,
+) -> anyhow::Result<()> {
+    let matches = dictionary.reverse_lookup(query);
+    if matches.is_empty() {
+        writeln!(out, "No entries for {query:?}")?;
+    }
+    for (entry, gloss, _) in matches {
+        let mut word = entry.word.to_string();
+        if let Some(homograph) = entry.homograph {
+            word += &homograph.to_string();
+        }
+        let pos = entry.pos.iter().map(|&pos| pos_label(pos)).join(", ");
+        writeln!(out, "{word}\t{pos}\t{}", gloss.text)?;
+    }
+    Ok(())
+}
+
+/// Reads queries from `input` until an empty line or the end of the input, and prints the
+/// results of each with `print`.
+pub fn repl(
+    dictionary: &Dictionary,
+    input: impl BufRead,
+    mut out: impl Write,
+    print: impl Fn(&Dictionary, &str, &mut dyn Write) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    write!(out, "> ")?;
+    out.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            break;
+        }
+        print(dictionary, &line, &mut out)?;
+        write!(out, "> ")?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_dictionary::DictionaryParser;
+
+    fn dictionary_of(texts: &[&str]) -> Dictionary {
+        let parser = DictionaryParser::new().unwrap();
+        let entries = (texts.iter())
+            .map(|text| parser.parse_entry(text).unwrap().unwrap().into_owned())
+            .collect();
+        Dictionary::new(entries)
+    }
+
+    #[test]
+    fn normalizes_full_and_half_width_characters() {
+        assert_eq!(normalize_width("ＡＢＣ　１２３（口語）"), "ABC 123(口語)");
+        assert_eq!(normalize_width("ｶﾞｲﾄﾞﾌﾞｯｸ"), "ガイドブック");
+        assert_eq!(normalize_width("ﾊﾟﾝ､ｳﾞｨｰﾅｽ｡"), "パン、ヴィーナス。");
+        // Marks after kana without a (semi-)voiced form stay marks
+        assert_eq!(normalize_width("ｱﾞﾏﾟ"), "ア゛マ゜");
+        assert_eq!(normalize_width("ガイド"), "ガイド");
+    }
+
+    #[test]
+    fn splits_senses_into_glosses_without_labels() {
+        let dictionary = dictionary_of(&[
+            "den1 [代] [ˈdæn’, dæn], dens [ˈdæn(’)s, dæns], det [ˈde, de],  dets [ˈdæds, dæds], de [ˈdi, di], dem [ˈdæm, dæm], deres [ˈdȧɹɔs, ˈdȧ:ɔs, dȧɔs]:［人称代名詞３人称］［すでに述べた動物・もの・ことに参照して］それ；［指示代名詞］［人・動物・もの・ことを指して］あれ，それ；あの，その；前者の；前者． ",
+            "varm [形] [ˈvα;m], varmt [ˈvα;md], varme [ˈvα:mə], varmere [ˈvα:mɔɔ],  varmest [ˈvα:məsd], varmeste [ˈvα:məsdə]: 温かい，暖かい；やや暑い；熱い；思いやりのある，心のこもった． ",
+        ]);
+        let glosses = |i: usize| {
+            (glosses(&dictionary.entries[i]).into_iter())
+                .map(|gloss| (gloss.sense, gloss.position, gloss.text))
+                .collect::<Vec<_>>()
+        };
+        let expected = |glosses: &[(usize, usize, &str)]| {
+            (glosses.iter())
+                .map(|&(sense, position, text)| (sense, position, text.to_owned()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            glosses(0),
+            expected(&[
+                (0, 0, "それ"),
+                (1, 0, "あれ"),
+                (1, 1, "それ"),
+                (2, 0, "あの"),
+                (2, 1, "その"),
+                (3, 0, "前者の"),
+                (4, 0, "前者"),
+            ])
+        );
+        assert_eq!(
+            glosses(1),
+            expected(&[
+                (0, 0, "温かい"),
+                (0, 1, "暖かい"),
+                (1, 0, "やや暑い"),
+                (2, 0, "熱い"),
+                (3, 0, "思いやりのある"),
+                (3, 1, "心のこもった"),
+            ])
+        );
+    }
+
+    #[test]
+    fn ranks_reverse_lookups_by_how_directly_the_gloss_matches() {
+        let dictionary = dictionary_of(&[
+            "hede [名] [ˈbi;l]: 暑さ；熱い風． ",
+            "ild [名] [ˈbi;l]: とても熱い． ",
+            "damp [名] [ˈbi;l]: 熱い湯気． ",
+            "varm [形] [ˈvα;m], varmt [ˈvα;md], varme [ˈvα:mə], varmere [ˈvα:mɔɔ],  varmest [ˈvα:məsd], varmeste [ˈvα:məsdə]: 温かい，暖かい；やや暑い；熱い；思いやりのある，心のこもった． ",
+            "glød [名] [ˈbi;l]: 赤く熱いもの，熱い． ",
+            "brand [名] [ˈbi;l]: 熱い． ",
+        ]);
+        let matches = (dictionary.reverse_lookup("熱い").into_iter())
+            .map(|(entry, gloss, kind)| (entry.word.to_string(), gloss.text.clone(), kind))
+            .collect::<Vec<_>>();
+        let expected = [
+            ("brand", "熱い", GlossMatch::Exact),
+            // An exact gloss wins over an earlier partial one
+            ("glød", "熱い", GlossMatch::Exact),
+            ("varm", "熱い", GlossMatch::Exact),
+            ("damp", "熱い湯気", GlossMatch::Prefix),
+            ("hede", "熱い風", GlossMatch::Prefix),
+            ("ild", "とても熱い", GlossMatch::Contains),
+        ]
+        .map(|(word, gloss, kind)| (word.to_owned(), gloss.to_owned(), kind));
+        assert_eq!(matches, expected);
+
+        let dictionary = dictionary_of(&[
+            "Amager [固] [ˈαˌmα;]: アマー［地名：コペンハーゲン南部の島．Kastrup空港がある］． ",
+        ]);
+        let words = (dictionary.reverse_lookup(" ｱﾏｰ ").into_iter())
+            .map(|(entry, _, kind)| (entry.word.to_string(), kind))
+            .collect::<Vec<_>>();
+        assert_eq!(words, [("Amager".to_owned(), GlossMatch::Exact)]);
+    }
+
+    #[test]
+    fn folds_case_danish_letters_and_accents() {
+        assert_eq!(fold("Idé"), "ide");
+        assert_eq!(fold("ide\u{301}"), "ide");
+        assert_eq!(fold("Ærø"), "aeroe");
+        assert_eq!(fold("Århus"), "aarhus");
+        assert_eq!(fold("A\u{30a}rhus"), "aarhus");
+        assert_eq!(fold("café crème"), "cafe creme");
+    }
+
+    #[test]
+    fn finds_words_typed_without_accents() {
+        let dictionary = dictionary_of(&[
+            "idé [名] [iˈde;], idéen [iˈde;ən], idéer [iˈde;ɔ], idéerne [iˈde;ɔnə]: 考え． ",
+            "øre1 [名] [ˈø:ɔ], øret [ˈø:ɔð], ører [ˈø:ɔ]/øren [ˈø:ɔn], ørerne [ˈø:ɔnə]: 耳． ",
+        ]);
+        let words = |query| {
+            (dictionary.lookup(query).into_iter())
+                .map(|(entry, _)| entry.word.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(words("ide"), ["idé"]);
+        assert_eq!(words("ideerne"), ["idé"]);
+        assert_eq!(words("oeret"), ["øre"]);
+    }
+}
@@ -1,163 +1,499 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    io::{BufRead, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
-use anyhow::{bail, Context};
+use anyhow::Context;
 use clap::Parser;
-use fs_err::File;
 use itertools::Itertools;
-use pdf::{
-    font::Font,
-    object::{PageRc, RcRef},
-};
+use pdf::object::PageRc;
 
 use danish_dictionary_parser::{
-    count_ops::count_ops,
-    decode_pdf_string::{decode_pdf_string, make_font_map, FontMap},
-    parse_dictionary::parse_dictionary,
-    walk_text::{each_text, TextEntry},
+    corrections::read_corrections,
+    count_ops::{count_ops, print_ops_table, OpsFormat},
+    debug_render::debug_render,
+    decode_pdf_string::{decode_pdf_string_lossy, DecodeStats, FontCache, FontCacheCounters},
+    explain_parse::print_explanation,
+    export_anki::{export_anki, NoteType},
+    inspect_fonts::{inspect_fonts, print_font_report},
+    layout::{decode_lines, extract_in_chunks, LayoutConfig, Line, Word, Words},
+    lookup::{print_lookup, print_reverse_lookup, repl, Dictionary},
+    output::{read_entries, write_atomically, write_entries, Format},
+    parse_dictionary::{parse_entries, patch, DictionaryParser, EntryBuf},
+    render_html::render_html,
+    review::{review, review_items},
+    schema::{check_schema, json_schema},
+    serve::serve,
 };
+
 #[derive(Parser)]
 struct Opts {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Print the text of each entry on its own line, as joined by the layout stage
+    Extract {
+        #[clap(flatten)]
+        extract: ExtractArgs,
+        /// Defaults to stdout
+        output_file: Option<PathBuf>,
+    },
+    /// Parse the entries and write them in the chosen format
+    Parse {
+        #[clap(flatten)]
+        extract: ExtractArgs,
+        /// Without an output file the entries are only checked for parse errors
+        output_file: Option<PathBuf>,
+        #[clap(long, value_enum, default_value = "json")]
+        format: Format,
+        /// Parse the entries with the corrections written by the review subcommand
+        #[clap(long)]
+        corrections: Option<PathBuf>,
+    },
+    /// Step through the entries that fail to parse or need a patch in a terminal UI, and save
+    /// corrections of their text
+    Review {
+        /// A PDF, or the output of the extract subcommand
+        source: PathBuf,
+        #[clap(long, default_value = "corrections.jsonl")]
+        corrections: PathBuf,
+    },
+    /// Match entries against the entry grammar one part at a time and show where each stops
+    /// matching
+    Explain {
+        /// The text of an entry as printed by the extract subcommand.  Without it, each line of
+        /// stdin is explained.
+        text: Option<String>,
+    },
+    /// Print the lines of each page as grouped by the layout stage, indenting the lines that
+    /// start an entry
+    DumpLines {
+        #[clap(flatten)]
+        pages: Pages,
+        /// Print every text object with its font and position instead
+        #[clap(long)]
+        verbose: bool,
+    },
+    /// Draw the layout stage's view of each page as an SVG file: text boxes by font, lines by
+    /// classification, the skipped footer and the indent bands
+    DebugRender {
+        #[clap(flatten)]
+        pages: Pages,
+        output_dir: PathBuf,
+    },
+    /// Count the content stream operators of each page, and how each font is drawn
+    Ops {
+        #[clap(flatten)]
+        pages: Pages,
+        #[clap(long, value_enum, default_value = "table")]
+        format: OpsFormat,
+    },
+    /// Describe the fonts of each page: type, encoding, where the Unicode mapping comes from and
+    /// which of the codes drawn with them are unmapped
+    Fonts {
+        #[clap(flatten)]
+        pages: Pages,
+    },
+    /// Look words up by headword or inflected form, ignoring case, accents and whether `å`, `æ`
+    /// and `ø` are spelled `aa`, `ae` and `oe`
+    Lookup {
+        /// Entries written with --format json or jsonl, or the PDF to parse them from
+        entries: PathBuf,
+        /// Without a word, queries are read from stdin until an empty line
+        word: Option<String>,
+    },
+    /// Find the headwords with a Japanese gloss, ranked by how directly the gloss matches
+    ReverseLookup {
+        /// Entries written with --format json or jsonl, or the PDF to parse them from
+        entries: PathBuf,
+        /// Without a word, queries are read from stdin until an empty line
+        word: Option<String>,
+    },
+    /// Serve lookups as a JSON API over HTTP
+    Serve {
+        /// Entries written with --format json or jsonl, or the PDF to parse them from
+        entries: PathBuf,
+        #[clap(long, default_value = "127.0.0.1:8080")]
+        address: String,
+    },
+    /// Convert entries written with --format json or jsonl to another format
+    Export {
+        entries: PathBuf,
+        output_file: PathBuf,
+        #[clap(long, value_enum)]
+        format: Format,
+    },
+    /// Build an Anki package from entries written with --format json or jsonl
+    Anki {
+        entries: PathBuf,
+        output_file: PathBuf,
+        #[clap(long, value_enum, default_value = "da-ja", use_value_delimiter = true)]
+        note_type: Vec<NoteType>,
+        #[clap(long, default_value = "Danish")]
+        deck: String,
+    },
+    /// Write a static website for browsing entries written with --format json or jsonl
+    RenderHtml {
+        entries: PathBuf,
+        output_dir: PathBuf,
+    },
+    /// Print the JSON Schema of the --format json output
+    Schema {
+        /// Instead of printing it, fail if the published schema at this path is out of date
+        #[clap(long)]
+        check: Option<PathBuf>,
+    },
+}
+
+/// The PDF and which of its pages to read.
+#[derive(clap::Args)]
+struct Pages {
     file: PathBuf,
-    output_file: Option<PathBuf>,
+    /// Only read this page
     #[clap(long)]
     page: Option<u32>,
-    #[clap(long)]
-    all_but: Option<usize>,
-    #[clap(long)]
-    count: bool,
-    #[clap(long)]
-    verbose: bool,
-    #[clap(long)]
-    dump_lines: bool,
+    /// Skip this many pages at the start
+    #[clap(long, alias = "all-but", conflicts_with = "page", default_value_t = 0)]
+    skip: u32,
 }
 
-fn main() -> anyhow::Result<()> {
-    let opts = Opts::parse();
-    let file = pdf::file::File::open(&opts.file)?;
-
-    if opts.count {
-        count_ops(&file)?;
-    } else if let Some(page) = opts.page {
-        process_page(&opts, &file, file.get_page(page)?)?;
-    } else {
-        let all_but = opts.all_but.unwrap_or(0);
-        let pages = file.pages().skip(all_but);
-        let words = get_words(&opts, &file, pages)?;
-        let entries = parse_dictionary(&words)?;
-        if let Some(path) = &opts.output_file {
-            serde_json::to_writer(File::create(path)?, &entries)?;
+impl Pages {
+    fn numbers(&self, file: &pdf::file::File<Vec<u8>>) -> Vec<u32> {
+        match self.page {
+            Some(page) => vec![page],
+            None => (self.skip..file.num_pages()).collect(),
         }
     }
+}
 
-    Ok(())
+#[derive(clap::Args)]
+struct ExtractArgs {
+    #[clap(flatten)]
+    pages: Pages,
+    /// Report the elapsed time and the effect of the font cache on stderr
+    #[clap(long)]
+    timings: bool,
+    /// Number of threads that extract pages concurrently
+    #[clap(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    jobs: u64,
 }
 
-fn get_words(
-    opts: &Opts,
-    file: &pdf::file::File<Vec<u8>>,
-    pages: impl Iterator<Item = pdf::error::Result<PageRc>>,
-) -> anyhow::Result<Vec<String>> {
-    let mut words = vec![];
-    for page in pages {
-        for line in process_page(opts, file, page?)? {
-            // line is guaranteed to be non-empty
-            // heading
-            if line[0].0.positions.glyph_size() > 11.0 {
-                continue;
+impl ExtractArgs {
+    fn all_pages(file: PathBuf) -> Self {
+        Self {
+            pages: Pages {
+                file,
+                page: None,
+                skip: 0,
+            },
+            timings: false,
+            jobs: 1,
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    match Opts::parse().command {
+        Command::Extract {
+            extract,
+            output_file,
+        } => {
+            let extract_to = |writer: Box<dyn Write>| -> anyhow::Result<()> {
+                let mut writer = BufWriter::new(writer);
+                with_words(&extract, |words| {
+                    for word in words {
+                        writeln!(writer, "{}", word?.text)?;
+                    }
+                    Ok(())
+                })?;
+                writer.flush()?;
+                Ok(())
+            };
+            match output_file {
+                Some(path) => write_atomically(&path, |path| {
+                    extract_to(Box::new(fs_err::File::create(path)?))
+                })?,
+                None => extract_to(Box::new(std::io::stdout()))?,
+            }
+        }
+        Command::Parse {
+            extract,
+            output_file,
+            format,
+            corrections,
+        } => {
+            let corrections = match &corrections {
+                Some(path) => read_corrections(path)?,
+                None => vec![],
+            };
+            with_words(&extract, |words| {
+                let mut entries = parse_entries(words, &corrections)?;
+                match &output_file {
+                    Some(path) => write_entries(format, path, entries),
+                    // Parse anyway to report the entries that cannot be parsed
+                    None => entries.try_for_each(|entry| entry.map(drop)),
+                }
+            })?
+        }
+        Command::Review {
+            source,
+            corrections,
+        } => {
+            let texts = if is_pdf(&source) {
+                let args = ExtractArgs::all_pages(source);
+                with_words(&args, |words| words.map_ok(|word| word.text).collect())?
+            } else {
+                (fs_err::read_to_string(source)?.lines())
+                    .map(str::to_owned)
+                    .collect_vec()
+            };
+            let existing = read_corrections(&corrections)?;
+            let items = review_items(texts, &existing)?;
+            review(items, existing, &corrections)?
+        }
+        Command::Explain { text } => {
+            let parser = DictionaryParser::new()?;
+            let explain = |text: &str| print_explanation(&parser.explain(patch(text)));
+            match text {
+                Some(text) => explain(&text),
+                None => {
+                    for line in std::io::stdin().lock().lines() {
+                        explain(&line?);
+                    }
+                }
             }
-            // empty line
-            if line.len() == 1 && line[0].1 == [" "] {
-                continue;
+        }
+        Command::DumpLines { pages, verbose } => {
+            let file = pdf::file::File::open(&pages.file)?;
+            let config = LayoutConfig::default();
+            let mut fonts = FontCache::default();
+            let mut stats = DecodeStats::default();
+            for page in pages.numbers(&file) {
+                let page = file.get_page(page)?;
+                dump_lines(verbose, &config, &file, &mut fonts, &page, &mut stats)?;
             }
-            if not_indented(&line[0].0)? {
-                words.push(String::new());
+            stats.print_summary();
+        }
+        Command::DebugRender { pages, output_dir } => {
+            let file = pdf::file::File::open(&pages.file)?;
+            let config = LayoutConfig::default();
+            let mut fonts = FontCache::default();
+            let mut stats = DecodeStats::default();
+            fs_err::create_dir_all(&output_dir)?;
+            for number in pages.numbers(&file) {
+                let page = file.get_page(number)?;
+                let svg = debug_render(&config, &file, &mut fonts, &page, &mut stats)?;
+                fs_err::write(output_dir.join(format!("page-{number:04}.svg")), svg)?;
+            }
+            stats.print_summary();
+        }
+        Command::Ops { pages, format } => {
+            let file = pdf::file::File::open(&pages.file)?;
+            let report = count_ops(&file, &pages.numbers(&file))?;
+            match format {
+                OpsFormat::Table => print_ops_table(&report),
+                OpsFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
             }
-            let word = words
-                .last_mut()
-                .context("Found indented line before the first line")?;
-            for (_, chars) in line {
-                for c in chars {
-                    word.push_str(&c);
+        }
+        Command::Fonts { pages } => {
+            let file = pdf::file::File::open(&pages.file)?;
+            for number in pages.numbers(&file) {
+                let page = file.get_page(number)?;
+                println!("Page {number}");
+                for report in inspect_fonts(&file, &page)? {
+                    print_font_report(&report);
                 }
             }
         }
+        Command::Lookup { entries, word } => {
+            let dictionary = Dictionary::new(load_entries(entries)?);
+            let stdout = std::io::stdout();
+            match word {
+                Some(word) => print_lookup(&dictionary, &word, stdout.lock())?,
+                None => repl(
+                    &dictionary,
+                    std::io::stdin().lock(),
+                    stdout.lock(),
+                    |d, word, out| print_lookup(d, word, out),
+                )?,
+            }
+        }
+        Command::ReverseLookup { entries, word } => {
+            let dictionary = Dictionary::new(load_entries(entries)?);
+            let stdout = std::io::stdout();
+            match word {
+                Some(word) => print_reverse_lookup(&dictionary, &word, stdout.lock())?,
+                None => repl(
+                    &dictionary,
+                    std::io::stdin().lock(),
+                    stdout.lock(),
+                    |d, word, out| print_reverse_lookup(d, word, out),
+                )?,
+            }
+        }
+        Command::Serve { entries, address } => {
+            serve(&Dictionary::new(load_entries(entries)?), &address)?
+        }
+        Command::Export {
+            entries,
+            output_file,
+            format,
+        } => write_entries(
+            format,
+            &output_file,
+            read_entries(&entries)?.into_iter().map(Ok),
+        )?,
+        Command::Anki {
+            entries,
+            output_file,
+            note_type,
+            deck,
+        } => export_anki(&output_file, &deck, &note_type, &read_entries(&entries)?)?,
+        Command::RenderHtml {
+            entries,
+            output_dir,
+        } => render_html(&output_dir, &read_entries(&entries)?)?,
+        Command::Schema { check: Some(path) } => check_schema(&path)?,
+        Command::Schema { check: None } => {
+            println!("{}", serde_json::to_string_pretty(&json_schema())?);
+        }
     }
-    Ok(words)
+    Ok(())
 }
 
-type ParsedTextEntry = (TextEntry, Vec<String>);
-fn process_page(
-    opts: &Opts,
-    file: &pdf::file::File<Vec<u8>>,
-    page: PageRc,
-) -> anyhow::Result<Vec<Vec<ParsedTextEntry>>> {
-    let lines = {
-        let mut last_y = f32::INFINITY;
-        let mut lines = vec![];
-        for entry in each_text(file, &page)?.skip_while(|e| {
-            e.as_ref()
-                .map_or(false, |e| e.positions.coordinates().y <= 50.0)
-        }) {
-            let entry = entry?;
-            let p = entry.positions.coordinates();
-            if p.y < last_y - 8.0 {
-                last_y = p.y;
-                lines.push(vec![entry]);
-            } else {
-                lines
-                    .last_mut()
-                    .expect("The branch above should run in the first iteration")
-                    .push(entry);
-            }
-        }
-        lines
+/// Reads entries written with --format json or jsonl, or parses them from all pages of a PDF.
+fn load_entries(path: PathBuf) -> anyhow::Result<Vec<EntryBuf>> {
+    if !is_pdf(&path) {
+        return read_entries(&path);
+    }
+    let args = ExtractArgs::all_pages(path);
+    with_words(&args, |words| parse_entries(words, &[])?.collect())
+}
+
+fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("pdf"))
+}
+
+/// Runs the layout stage over the selected pages and hands the joined entries to `f`, then
+/// reports the decoding statistics.
+fn with_words<T>(
+    args: &ExtractArgs,
+    f: impl FnOnce(&mut dyn Iterator<Item = anyhow::Result<Word>>) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let start = Instant::now();
+    let file = pdf::file::File::open(&args.pages.file)?;
+    let config = LayoutConfig::default();
+    let pages = args.pages.numbers(&file);
+    let mut fonts = FontCache::default();
+    let mut stats = DecodeStats::default();
+
+    let result = if args.jobs > 1 {
+        let (lines, counters) = get_lines_parallel(args, &config, &pages, &mut stats)?;
+        fonts.counters.merge(counters);
+        f(&mut Words::new(lines.into_iter().map(Ok)))?
+    } else {
+        let lines = pages.iter().map(|&page| {
+            let page = file.get_page(page)?;
+            page_lines(&config, &file, &mut fonts, &page, &mut stats)
+        });
+        f(&mut Words::new(lines.flatten_ok()))?
     };
 
-    let fonts = make_font_map(file, &page)?;
-    if opts.dump_lines {
-        dump_lines(opts, &fonts, &lines)?;
+    stats.print_summary();
+    if args.timings {
+        fonts.counters.print_summary();
+        eprintln!("Finished in {:.3?}", start.elapsed());
     }
+    Ok(result)
+}
 
-    let mut parsed_lines = vec![];
-    for line in lines {
-        let mut parsed_line = vec![];
-        for entry in line {
-            let (font, map) = fonts
-                .get(entry.font.as_str())
-                .with_context(|| format!("Font {:?} not found", entry.font))?;
-            let strings = decode_pdf_string(map, font.subtype, &entry.text)?
-                .map(|e| e.to_owned())
-                .collect_vec();
-            parsed_line.push((entry, strings));
-        }
-        parsed_lines.push(parsed_line);
+/// Extracts the lines of each chunk of pages on its own thread with its own file handle and
+/// font cache.
+fn get_lines_parallel(
+    args: &ExtractArgs,
+    config: &LayoutConfig,
+    pages: &[u32],
+    stats: &mut DecodeStats,
+) -> anyhow::Result<(Vec<Line>, FontCacheCounters)> {
+    let results = extract_in_chunks(pages, args.jobs as usize, |chunk| {
+        extract_lines(args, config, chunk)
+    })?;
+
+    let mut lines = vec![];
+    let mut counters = FontCacheCounters::default();
+    for (chunk_lines, chunk_stats, chunk_counters) in results {
+        lines.extend(chunk_lines);
+        stats.merge(chunk_stats);
+        counters.merge(chunk_counters);
     }
-    Ok(parsed_lines)
+    Ok((lines, counters))
+}
+
+fn extract_lines(
+    args: &ExtractArgs,
+    config: &LayoutConfig,
+    pages: &[u32],
+) -> anyhow::Result<(Vec<Line>, DecodeStats, FontCacheCounters)> {
+    let file = pdf::file::File::open(&args.pages.file)?;
+    let mut fonts = FontCache::default();
+    let mut stats = DecodeStats::default();
+    let mut lines = vec![];
+    for &page in pages {
+        let page = file.get_page(page)?;
+        lines.extend(page_lines(config, &file, &mut fonts, &page, &mut stats)?);
+    }
+    Ok((lines, stats, fonts.counters))
+}
+
+fn page_lines(
+    config: &LayoutConfig,
+    file: &pdf::file::File<Vec<u8>>,
+    fonts: &mut FontCache,
+    page: &PageRc,
+    stats: &mut DecodeStats,
+) -> anyhow::Result<Vec<Line>> {
+    let lines = config.group_lines(file, page)?;
+    let fonts = fonts.page_fonts(file, page)?;
+    config.lines(decode_lines(&fonts, lines, stats)?)
 }
 
 fn dump_lines(
-    opts: &Opts,
-    fonts: &HashMap<&str, (RcRef<Font>, FontMap)>,
-    lines: &[Vec<TextEntry>],
+    verbose: bool,
+    config: &LayoutConfig,
+    file: &pdf::file::File<Vec<u8>>,
+    fonts: &mut FontCache,
+    page: &PageRc,
+    stats: &mut DecodeStats,
 ) -> Result<(), anyhow::Error> {
+    let lines = config.group_lines(file, page)?;
+    let fonts = fonts.page_fonts(file, page)?;
     for line in lines {
-        if opts.verbose {
+        if verbose {
             println!("=============");
         }
         if let Some(entry) = line.get(0) {
-            if !opts.verbose {
-                let a = if not_indented(entry)? { "    " } else { "" };
+            if !verbose {
+                let a = if config.not_indented(entry)? {
+                    "    "
+                } else {
+                    ""
+                };
                 print!("{a:}");
             }
         }
-        if !opts.verbose {
+        if !verbose {
             print!("[");
         }
         for entry in line {
-            let (font, map) = fonts
+            let (_, map) = fonts
                 .get(entry.font.as_str())
                 .with_context(|| format!("Font {:?} not found", entry.font))?;
-            if opts.verbose {
+            if verbose {
                 print!(
                     "{:?}\t{:?}\t{:.3?}",
                     entry.font,
@@ -165,28 +501,17 @@ fn dump_lines(
                     entry.positions.coordinates()
                 );
             }
-            for s in decode_pdf_string(map, font.subtype, &entry.text)? {
+            for s in decode_pdf_string_lossy(map, &entry.text, stats) {
                 print!("{s}");
             }
-            if opts.verbose {
+            if verbose {
                 println!();
             }
         }
-        if !opts.verbose {
+        if !verbose {
             print!("]");
         }
         println!();
     }
     Ok(())
 }
-
-fn not_indented(first_entry: &TextEntry) -> anyhow::Result<bool> {
-    Ok(match first_entry.positions.coordinates().x {
-        // Hack: manual indentation
-        _ if first_entry.text.as_bytes() == b" " => false,
-        x if (70.5..71.5).contains(&x) => true,
-        x if (80.0..82.5).contains(&x) => false,
-        x if (91.5..92.5).contains(&x) => false,
-        x => bail!("Unexpected x coordinates: {x}"),
-    })
-}
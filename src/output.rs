@@ -0,0 +1,422 @@
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::Context;
+use fs_err::File;
+use itertools::Itertools;
+use serde::{de::IgnoredAny, Deserialize};
+
+use crate::{
+    export_epub::export_epub,
+    export_kaikki::kaikki_records,
+    export_sqlite::export_sqlite,
+    export_stardict::export_stardict,
+    export_tei::export_tei,
+    export_yomitan::export_yomitan,
+    parse_dictionary::{EntryBuf, NounCount, OtherForm, Pos},
+    schema::{check_version, Document, Record, VersionedEntry, SCHEMA_VERSION},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum Format {
+    /// A single JSON document with the schema version and the entries
+    Json,
+    /// One JSON object per line
+    Jsonl,
+    /// JSON Lines in the wiktextract schema used by kaikki.org
+    Kaikki,
+    /// One row per headword
+    Csv,
+    /// Same as csv, separated by tabs
+    Tsv,
+    /// SQLite database with full-text search
+    Sqlite,
+    /// StarDict dictionary (.ifo, .idx, .dict and .syn next to the output file)
+    Stardict,
+    /// Yomitan dictionary archive (.zip)
+    Yomitan,
+    /// TEI Lex-0 XML
+    Tei,
+    /// EPUB 3 dictionary with Kindle lookup markup
+    Epub,
+}
+
+/// The formats that are written one entry at a time, to any writer.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum StreamFormat {
+    Json,
+    Jsonl,
+    Kaikki,
+    Csv,
+    Tsv,
+}
+
+/// Number of `other_forms` columns in the flattened formats.  Entries with more forms have the
+/// rest in the last column, separated by `, `.
+const OTHER_FORM_COLUMNS: usize = 6;
+/// Number of `other_adjective_forms` columns in the flattened formats (comparative, superlative
+/// and definite superlative).  Entries with more forms have the rest in the last column.
+const OTHER_ADJECTIVE_FORM_COLUMNS: usize = 3;
+
+/// Writes the entries to `path`, which is only created once all entries are written, so that an
+/// entry failing to parse never leaves a truncated file behind.
+pub fn write_entries(
+    format: Format,
+    path: &Path,
+    entries: impl Iterator<Item = anyhow::Result<EntryBuf>>,
+) -> anyhow::Result<()> {
+    write_atomically(path, |path| export(format, path, entries))
+}
+
+fn export(
+    format: Format,
+    path: &Path,
+    entries: impl Iterator<Item = anyhow::Result<EntryBuf>>,
+) -> anyhow::Result<()> {
+    let format = match format {
+        Format::Json => StreamFormat::Json,
+        Format::Jsonl => StreamFormat::Jsonl,
+        Format::Kaikki => StreamFormat::Kaikki,
+        Format::Csv => StreamFormat::Csv,
+        Format::Tsv => StreamFormat::Tsv,
+        Format::Sqlite => return export_sqlite(path, entries),
+        Format::Stardict => return export_stardict(path, entries),
+        Format::Yomitan => return export_yomitan(path, entries),
+        Format::Tei => return export_tei(path, entries),
+        Format::Epub => return export_epub(path, entries),
+    };
+    write_stream(format, BufWriter::new(File::create(path)?), entries)
+}
+
+/// Runs `write` with a path of the same name in a directory next to `path`, then moves the files
+/// written there into place if it succeeds, and removes them otherwise.  Formats that write
+/// several files, like StarDict, have all of them moved.
+pub fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&Path) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let file_name = path
+        .file_name()
+        .context("The output path has no file name")?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut partial_name = OsString::from(".");
+    partial_name.push(file_name);
+    partial_name.push(".partial");
+    let partial = dir.join(partial_name);
+    if partial.exists() {
+        fs_err::remove_dir_all(&partial)?;
+    }
+    fs_err::create_dir(&partial)?;
+
+    let result = write(&partial.join(file_name)).and_then(|()| {
+        for file in fs_err::read_dir(&partial)? {
+            let file = file?;
+            fs_err::rename(file.path(), dir.join(file.file_name()))?;
+        }
+        Ok(())
+    });
+    let cleanup = fs_err::remove_dir_all(&partial);
+    result?;
+    cleanup?;
+    Ok(())
+}
+
+/// Reads entries written with [`Format::Json`] or [`Format::Jsonl`], including files written
+/// before the output was versioned.
+pub fn read_entries(path: &Path) -> anyhow::Result<Vec<EntryBuf>> {
+    let text = fs_err::read_to_string(path)?;
+    let text = text.trim_start();
+    if text.starts_with('[') {
+        check_version(None)?;
+        return serde_json::from_str(text).context("Invalid JSON array of entries");
+    }
+    if !is_json_lines(text) {
+        let document: Document =
+            serde_json::from_str(text).context("Invalid JSON document of entries")?;
+        check_version(Some(document.schema_version))?;
+        return Ok(document.entries);
+    }
+    (text.lines().enumerate())
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let record: VersionedEntry = serde_json::from_str(line)
+                .with_context(|| format!("Invalid JSON Lines entry on line {}", i + 1))?;
+            check_version(record.schema_version)?;
+            Ok(record.entry)
+        })
+        .collect()
+}
+
+/// Whether the first line is an entry on its own.  A document is either spread over many lines
+/// or has its entries under `entries`.  An empty file is JSON Lines without entries.
+fn is_json_lines(text: &str) -> bool {
+    #[derive(Deserialize)]
+    struct Shape {
+        entries: Option<IgnoredAny>,
+    }
+    let first_line = match text.lines().next() {
+        Some(line) => line,
+        None => return true,
+    };
+    matches!(
+        serde_json::from_str::<Shape>(first_line),
+        Ok(Shape { entries: None })
+    )
+}
+
+fn write_stream(
+    format: StreamFormat,
+    mut writer: impl Write,
+    entries: impl Iterator<Item = anyhow::Result<EntryBuf>>,
+) -> anyhow::Result<()> {
+    match format {
+        StreamFormat::Json => {
+            // Same output as serializing a `Document`, but one entry at a time
+            write!(writer, r#"{{"schema_version":{SCHEMA_VERSION},"entries":["#)?;
+            for (i, entry) in entries.enumerate() {
+                if i > 0 {
+                    writer.write_all(b",")?;
+                }
+                serde_json::to_writer(&mut writer, &entry?)?;
+            }
+            writer.write_all(b"]}")?;
+        }
+        StreamFormat::Jsonl => {
+            for entry in entries {
+                let record = Record {
+                    schema_version: SCHEMA_VERSION,
+                    entry: &entry?,
+                };
+                serde_json::to_writer(&mut writer, &record)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        StreamFormat::Kaikki => {
+            for entry in entries {
+                for record in kaikki_records(&entry?) {
+                    serde_json::to_writer(&mut writer, &record)?;
+                    writer.write_all(b"\n")?;
+                }
+            }
+        }
+        StreamFormat::Csv | StreamFormat::Tsv => {
+            let delimiter = if format == StreamFormat::Csv {
+                b','
+            } else {
+                b'\t'
+            };
+            let mut writer = csv::WriterBuilder::new()
+                .delimiter(delimiter)
+                .from_writer(writer);
+            writer.write_record(flat_header())?;
+            for entry in entries {
+                writer.write_record(flatten(&entry?))?;
+            }
+            writer.flush()?;
+            return Ok(());
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn flat_header() -> Vec<String> {
+    let other_forms = (1..=OTHER_FORM_COLUMNS).map(|i| format!("form_{i}"));
+    let other_adjective_forms =
+        (1..=OTHER_ADJECTIVE_FORM_COLUMNS).map(|i| format!("adjective_form_{i}"));
+    ["word", "pos", "pronunciation"]
+        .into_iter()
+        .map(String::from)
+        .chain(other_forms)
+        .chain(other_adjective_forms)
+        .collect()
+}
+
+fn flatten(entry: &EntryBuf) -> Vec<String> {
+    let pos = entry.pos.iter().map(|&pos| pos_label(pos)).join(";");
+    let pronunciation = entry.pronunciations.first().map_or("", |s| s);
+    let mut record = vec![entry.word.to_string(), pos, pronunciation.into()];
+    for (forms, columns) in [
+        (&entry.other_forms, OTHER_FORM_COLUMNS),
+        (&entry.other_adjective_forms, OTHER_ADJECTIVE_FORM_COLUMNS),
+    ] {
+        let split = forms.len().min(columns - 1);
+        let (own_columns, rest) = forms.split_at(split);
+        record.extend(own_columns.iter().map(form_cell));
+        record.push(rest.iter().map(form_cell).join(", "));
+        record.resize(record.len() + columns - 1 - split, String::new());
+    }
+    record
+}
+
+/// The form and its slashed alternatives, e.g. `ører/øren`.
+pub fn form_cell(form: &OtherForm) -> String {
+    std::iter::once(&form.word)
+        .chain(form.slashed.iter().map(|f| &f.word))
+        .join("/")
+}
+
+pub fn pos_label(pos: Pos) -> &'static str {
+    match pos {
+        Pos::Noun(None) => "noun",
+        Pos::Noun(Some(NounCount::Single)) => "noun (singular)",
+        Pos::Noun(Some(NounCount::Multiple)) => "noun (plural)",
+        Pos::ProperNoun => "proper noun",
+        Pos::Pronoun => "pronoun",
+        Pos::Numeral => "numeral",
+        Pos::Adjective(false) => "adjective",
+        Pos::Adjective(true) => "adjective (invariant)",
+        Pos::Verb => "verb",
+        Pos::Adverb => "adverb",
+        Pos::Preposition => "preposition",
+        Pos::Conjunction => "conjunction",
+        Pos::Interjection => "interjection",
+        Pos::InfinitiveMarker => "infinitive marker",
+        Pos::Article => "article",
+        Pos::IndefiniteArticle => "indefinite article",
+        Pos::FormalSubject => "formal subject",
+    }
+}
+
+/// Makes repeated ids unique with a suffix, `-2` for the second occurrence and so on.  The first
+/// occurrence keeps its id, and a suffixed id never takes an id that occurs in `ids`.
+pub fn unique_ids(ids: Vec<String>) -> Vec<String> {
+    let mut taken: HashSet<String> = ids.iter().cloned().collect();
+    let mut seen = HashSet::new();
+    ids.into_iter()
+        .map(|id| {
+            if seen.insert(id.clone()) {
+                return id;
+            }
+            let unique = (2..)
+                .map(|i| format!("{id}-{i}"))
+                .find(|candidate| !taken.contains(candidate))
+                .unwrap();
+            taken.insert(unique.clone());
+            unique
+        })
+        .collect()
+}
+
+/// Escapes text for HTML and XML content and attribute values.
+pub fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_dictionary::DictionaryParser;
+
+    fn entries() -> Vec<EntryBuf> {
+        let parser = DictionaryParser::new().unwrap();
+        [
+            "bil [名] [ˈbi;l], bilen [ˈbi:lən]: 自動車． ",
+            "Amager [固] [ˈαˌmα;]: アマー． ",
+        ]
+        .into_iter()
+        .map(|text| parser.parse_entry(text).unwrap().unwrap().into_owned())
+        .collect()
+    }
+
+    fn read(text: &str) -> anyhow::Result<Vec<EntryBuf>> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("entries");
+        fs_err::write(&path, text).unwrap();
+        read_entries(&path)
+    }
+
+    fn words(entries: &[EntryBuf]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.word.as_ref()).collect()
+    }
+
+    fn stream(format: StreamFormat) -> String {
+        let mut out = vec![];
+        write_stream(format, &mut out, entries().into_iter().map(Ok)).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn flattens_extra_forms_into_the_last_column() {
+        let parser = DictionaryParser::new().unwrap();
+        let forms = ('a'..='h').map(|c| format!(", form{c} [x]")).join("");
+        let text = format!("ord [代] [x]{forms}: 語． ");
+        let entry = parser.parse_entry(&text).unwrap().unwrap().into_owned();
+        assert_eq!(entry.other_forms.len(), 8);
+
+        let record = flatten(&entry);
+        assert_eq!(record.len(), flat_header().len());
+        assert_eq!(record[3..8], ["forma", "formb", "formc", "formd", "forme"]);
+        assert_eq!(record[8], "formf, formg, formh");
+        assert_eq!(record[9..], ["", "", ""]);
+
+        let record = flatten(&entries()[0]);
+        assert_eq!(record.len(), flat_header().len());
+        assert_eq!(record[3..5], ["bilen", ""]);
+    }
+
+    #[test]
+    fn writes_nothing_when_an_entry_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        for format in [Format::Json, Format::Csv, Format::Tei, Format::Stardict] {
+            let path = dir.path().join("entries.out");
+            let failing =
+                (entries().into_iter().map(Ok)).chain([Err(anyhow::anyhow!("Could not parse"))]);
+            assert!(write_entries(format, &path, failing).is_err());
+            assert_eq!(
+                fs_err::read_dir(dir.path()).unwrap().count(),
+                0,
+                "{format:?}"
+            );
+
+            write_entries(format, &path, entries().into_iter().map(Ok)).unwrap();
+            let written = fs_err::read_dir(dir.path()).unwrap().count();
+            assert_eq!(written, if format == Format::Stardict { 4 } else { 1 });
+            for file in fs_err::read_dir(dir.path()).unwrap() {
+                fs_err::remove_file(file.unwrap().path()).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn reads_back_what_is_written() {
+        let document = stream(StreamFormat::Json);
+        assert_eq!(words(&read(&document).unwrap()), ["bil", "Amager"]);
+        let pretty = serde_json::to_string_pretty(&Document {
+            schema_version: SCHEMA_VERSION,
+            entries: entries(),
+        })
+        .unwrap();
+        assert_eq!(words(&read(&pretty).unwrap()), ["bil", "Amager"]);
+        assert_eq!(
+            words(&read(&stream(StreamFormat::Jsonl)).unwrap()),
+            ["bil", "Amager"]
+        );
+        let unversioned = serde_json::to_string(&entries()).unwrap();
+        assert_eq!(words(&read(&unversioned).unwrap()), ["bil", "Amager"]);
+        assert!(read("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn reports_errors_of_the_detected_shape() {
+        let document = stream(StreamFormat::Json);
+        let truncated = &document[..document.find("\"see_also\"").unwrap()];
+        let error = format!("{:#}", read(truncated).unwrap_err());
+        assert!(error.starts_with("Invalid JSON document"), "{error}");
+
+        let jsonl = stream(StreamFormat::Jsonl);
+        let corrupt = jsonl.replacen("\"word\"", "\"wrd\"", 2);
+        let error = format!("{:#}", read(&corrupt).unwrap_err());
+        assert!(
+            error.starts_with("Invalid JSON Lines entry on line 1"),
+            "{error}"
+        );
+    }
+}
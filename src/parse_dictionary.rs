@@ -1,240 +1,508 @@
+use std::{borrow::Cow, collections::HashMap};
+
 use anyhow::bail;
 use itertools::Itertools;
 use regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-pub fn parse_dictionary(words: &[String]) -> anyhow::Result<Vec<Entry>> {
-    let extended_word_chars = r"[a-zA-Z7éøæåØÆÅ\-.,’()/＝]+";
-    let extended_heading_words = format!(
-        r"(?x)
-            {extended_word_chars}
-            ((?-x) (?x) {extended_word_chars})*
-        "
-    );
-    let word_chars = r"[a-zA-ZøæåØÆÅ]+";
-    let heading_words = format!(
-        r"(?x)
-            {word_chars}
-            ((?-x) (?x) {word_chars})*
-        "
-    );
-    let pos = r"(?x)
-            (
-                [\[［](
-                    名(・[単複])?
-                    | 固 | 代 | 数 | 形 | 動 | 副 | 前 | 接 | 間
-                    | 不定詞マーカー | 冠 | 不定冠詞 | 形式主語
-                )[\]］]
-                | \[形\]\s*\[無変化\]
-            )
-        ";
-    let pronunciation = r"(?x)
-            ([
-                a-z’åȧäæöøα:
-                ˈˌəɑðŋɔgnɹ
-                \u0329\u030A\u0308
-                \u0227ᒑ;\u0283
-                ()
-                \uF0D9
-            ]|(?-x) (?x))+
-        ";
-    let pronunciation_list = format!(
-        r"(?x)
-            {pronunciation}
-            ([,，]\s* {pronunciation} )*
-        "
-    );
-    let word_and_pronunciation = format!(
-        r"(?x)
-            (?P<wp_word> {heading_words} ) \s*
-            \[ (?P<wp_pronunciation> {pronunciation_list} ) \] \s*
-        "
-    );
-    let word_and_pronunciation_regex = Regex::new(&word_and_pronunciation)?;
-    let other_forms = format!(
-        r"(?x)
-            ,\s*
-            (?P<of_suffix_marker> \+ )?
-            (?P<of_word> {extended_heading_words})
-            (?P<of_imparative> ! )? \s*
-            \[ (?P<of_pronunciation> {pronunciation_list} ) \] \s*
-            (?P<of_slahsed> / {word_and_pronunciation} )*
-        "
-    );
-    let other_forms_regex = Regex::new(&other_forms)?;
-    let other_adjective_forms = format!(
-        r"(?x)
-            ,\s*
-            (?P<oaf_word> {heading_words} )
-            ( \s* \[ (?P<oaf_pronunciation> {pronunciation_list} ) \] \s* )?
-            (?P<oaf_slashed> (/ {heading_words} )* ) \s*
-        "
-    );
-    let other_adjective_forms_regex = Regex::new(&other_adjective_forms)?;
-    let entry_pattern = format!(
-        r"(?x)
-            ^
-            \+?
-            (?P<word> {extended_heading_words})
-            (\s*[1-4])?
-            \s*
-
-            (?P<pos> 
-                {pos}
-                ( [,，]\s* {pos} )*
-            )?
-            \s*
+use crate::{
+    corrections::Correction,
+    explain_parse::{explain, Component, Explanation},
+    layout::Word,
+};
 
-            \[ (?P<pronunciation> {pronunciation_list} ) \] \s*
+pub fn parse_dictionary(words: &[String]) -> anyhow::Result<Vec<Entry>> {
+    let parser = DictionaryParser::new()?;
+    words
+        .iter()
+        .filter_map(|word| parser.parse_entry(word).transpose())
+        .collect()
+}
 
-            (?P<invariant_adjective> [\[［] 不変化 [\]］] \s* )?
-            (en\s*)?
+/// Parses entries one at a time as the words arrive, so that the whole document never has to be
+/// kept in memory.
+pub fn parse_entries<I>(
+    words: I,
+    corrections: &[Correction],
+) -> anyhow::Result<impl Iterator<Item = anyhow::Result<EntryBuf>>>
+where
+    I: IntoIterator<Item = anyhow::Result<Word>>,
+{
+    let parser = DictionaryParser::new()?.with_corrections(corrections);
+    Ok(words.into_iter().filter_map(move |word| {
+        let word = match word {
+            Ok(word) => word,
+            Err(e) => return Some(Err(e)),
+        };
+        parser
+            .parse_entry(&word.text)
+            .map(|entry| {
+                entry.map(|entry| Entry {
+                    section: word.section.map(Into::into),
+                    ..entry.into_owned()
+                })
+            })
+            .transpose()
+    }))
+}
 
-            (?P<other_forms> ( {other_forms} )* )
+pub struct DictionaryParser {
+    regex: Regex,
+    cross_reference_regex: Regex,
+    redirect_regex: Regex,
+    word_and_pronunciation_regex: Regex,
+    other_forms_regex: Regex,
+    other_adjective_forms_regex: Regex,
+    /// The parts of `regex`, in order.
+    steps: Vec<(Component, Regex)>,
+    /// Replacements for the text of entries, by the text as extracted.
+    corrections: HashMap<String, String>,
+}
 
-            (?P<other_adjective_forms> ( {other_adjective_forms} )*)
+impl DictionaryParser {
+    pub fn new() -> anyhow::Result<Self> {
+        let extended_word_chars = r"[a-zA-Z7éøæåØÆÅ\-.,’()/＝]+";
+        let extended_heading_words = format!(
+            r"(?x)
+                {extended_word_chars}
+                ((?-x) (?x) {extended_word_chars})*
+            "
+        );
+        let word_chars = r"[a-zA-ZøæåØÆÅ]+";
+        let heading_words = format!(
+            r"(?x)
+                {word_chars}
+                ((?-x) (?x) {word_chars})*
+            "
+        );
+        let pos = r"(?x)
+                (
+                    [\[［](
+                        名(・[単複])?
+                        | 固 | 代 | 数 | 形 | 動 | 副 | 前 | 接 | 間
+                        | 不定詞マーカー | 冠 | 不定冠詞 | 形式主語
+                    )[\]］]
+                    | \[形\]\s*\[無変化\]
+                )
+            ";
+        let pronunciation = r"(?x)
+                ([
+                    a-z’åȧäæöøα:
+                    ˈˌəɑðŋɔgnɹ
+                    \u0329\u030A\u0308
+                    \u0227ᒑ;\u0283
+                    ()
+                    \uF0D9
+                ]|(?-x) (?x))+
+            ";
+        let pronunciation_list = format!(
+            r"(?x)
+                {pronunciation}
+                ([,，]\s* {pronunciation} )*
+            "
+        );
+        let word_and_pronunciation = format!(
+            r"(?x)
+                (?P<wp_word> {heading_words} ) \s*
+                \[ (?P<wp_pronunciation> {pronunciation_list} ) \] \s*
+            "
+        );
+        let word_and_pronunciation_regex = Regex::new(&word_and_pronunciation)?;
+        let other_forms = format!(
+            r"(?x)
+                ,\s*
+                (?P<of_suffix_marker> \+ )?
+                (?P<of_word> {extended_heading_words})
+                (?P<of_imparative> ! )? \s*
+                \[ (?P<of_pronunciation> {pronunciation_list} ) \] \s*
+                (?P<of_slashed> ( / {word_and_pronunciation} )* )
+            "
+        );
+        let other_forms_regex = Regex::new(&other_forms)?;
+        let other_adjective_forms = format!(
+            r"(?x)
+                ,\s*
+                (?P<oaf_word> {heading_words} )
+                ( \s* \[ (?P<oaf_pronunciation> {pronunciation_list} ) \] \s* )?
+                (?P<oaf_slashed> (/ {heading_words} )* ) \s*
+            "
+        );
+        let other_adjective_forms_regex = Regex::new(&other_adjective_forms)?;
+        // The parts of the entry grammar.  The entry regex is made of them, and each one is also
+        // matched on its own to explain where an entry stops matching.
+        let entry_parts = [
+            (
+                Component::Headword,
+                format!(r"\+? (?P<word> {extended_heading_words})"),
+            ),
+            (
+                Component::Homograph,
+                r"\s* (?P<homograph> [1-4] )".to_owned(),
+            ),
+            (
+                Component::PartsOfSpeech,
+                format!(r"\s* (?P<pos> {pos} ( [,，]\s* {pos} )* )"),
+            ),
+            (
+                Component::Pronunciations,
+                format!(r"\s* \[ (?P<pronunciation> {pronunciation_list} ) \] \s*"),
+            ),
+            (
+                Component::Invariant,
+                r"(?P<invariant_adjective> [\[［] 不変化 [\]］] \s* )".to_owned(),
+            ),
+            (Component::Gender, r"en\s*".to_owned()),
+            (
+                Component::OtherForms,
+                format!("(?P<other_forms> ( {other_forms} )* )"),
+            ),
+            (
+                Component::OtherAdjectiveForms,
+                format!("(?P<other_adjective_forms> ( {other_adjective_forms} )* )"),
+            ),
+            (Component::ParenthesizedGender, r"\(en\)".to_owned()),
+            (Component::Colon, "[:：]".to_owned()),
+        ];
+        let entry_pattern = (entry_parts.iter())
+            .map(|(component, pattern)| {
+                if component.optional() {
+                    format!("( {pattern} )?")
+                } else {
+                    pattern.clone()
+                }
+            })
+            .join("\n");
+        let entry_pattern = format!("(?x) ^ {entry_pattern}");
+        let regex = Regex::new(&entry_pattern)?;
+        let cross_reference = format!(
+            r"(?x)
+                → \s*
+                (?P<xr_word> {heading_words})
+                (\s*(?P<xr_homograph> [1-4] ))?
+            "
+        );
+        let cross_reference_regex = Regex::new(&cross_reference)?;
+        // Entries that only point at another entry, e.g. `ører → øre1`
+        let redirect_pattern = format!(
+            r"(?x)
+                ^
+                \+?
+                (?P<word> {extended_heading_words})
+                (\s*(?P<homograph> [1-4] ))?
+                \s*
+                (?P<pos> {pos} ( [,，]\s* {pos} )* )?
+                \s*
+                ( \[ (?P<pronunciation> {pronunciation_list} ) \] \s* )?
+                [:：]? \s*
+                {cross_reference}
+                \s* [．.]? \s*
+                $
+            "
+        );
+        let redirect_regex = Regex::new(&redirect_pattern)?;
+        let steps = (entry_parts.into_iter())
+            .map(|(component, pattern)| Ok((component, Regex::new(&format!("(?x) ^ {pattern}"))?)))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            regex,
+            cross_reference_regex,
+            redirect_regex,
+            word_and_pronunciation_regex,
+            other_forms_regex,
+            other_adjective_forms_regex,
+            steps,
+            corrections: HashMap::new(),
+        })
+    }
 
-            ( \(en\) )?
+    /// Matches the parts of the entry grammar one at a time, to find where `text` stops
+    /// matching.  `text` is taken as is, without [`patch`].
+    pub fn explain<'a>(&self, text: &'a str) -> Explanation<'a> {
+        explain(&self.steps, text)
+    }
 
-            [:：]
-        "
-    );
-    let regex = Regex::new(&entry_pattern)?;
+    /// Parses the entries with these texts as if they had the corrected texts, which take
+    /// precedence over [`patch`].
+    pub fn with_corrections(mut self, corrections: &[Correction]) -> Self {
+        self.corrections
+            .extend((corrections.iter()).map(|c| (c.original.clone(), c.corrected.clone())));
+        self
+    }
 
-    let mut ret = vec![];
+    /// Returns `None` for entries that are recognized but not supported yet.
+    pub fn parse_entry<'a>(&self, word: &'a str) -> anyhow::Result<Option<Entry<'a>>> {
+        if let Some(corrected) = self.corrections.get(word) {
+            let entry = self.parse_patched(word, corrected)?;
+            return Ok(entry.map(Entry::into_owned));
+        }
+        self.parse_patched(word, patch(word))
+    }
 
-    for word in words {
-        #[allow(clippy::if_same_then_else)]
-        if let Some(res) = regex.captures(patch(word)) {
+    fn parse_patched<'a>(&self, word: &str, patched: &'a str) -> anyhow::Result<Option<Entry<'a>>> {
+        if let Some(res) = self.regex.captures(patched) {
+            let definition = patched[res.get(0).unwrap().end()..].trim();
             let word = res.name("word").unwrap().as_str();
+            let homograph = res
+                .name("homograph")
+                .map(|x| x.as_str().parse())
+                .transpose()?;
             let pos = res.name("pos").map(|x| x.as_str());
             let pronunciation = res.name("pronunciation").unwrap().as_str();
             let invariant_adjective = res.name("invariant_adjective").is_some();
-            let other_forms = res.name("other_forms").unwrap().as_str();
-            let other_adjective_forms = res.name("other_adjective_forms").unwrap().as_str();
+            let other_forms = res.name("other_forms").map_or("", |x| x.as_str());
+            let other_adjective_forms =
+                res.name("other_adjective_forms").map_or("", |x| x.as_str());
 
-            let comma = &[',', '，'];
-            let pos = pos.map_or_else(Vec::new, |pos| {
-                use Pos::*;
-                let v = pos.split(comma).map(|pos| {
-                    match pos
-                        .trim()
-                        .strip_prefix(&['[', '［'])
-                        .unwrap()
-                        .strip_suffix(&[']', '］'])
-                        .unwrap()
-                    {
-                        "名" => Noun(None),
-                        "名・単" => Noun(Some(NounCount::Single)),
-                        "名・複" => Noun(Some(NounCount::Multiple)),
-                        "固" => ProperNoun,
-                        "代" => Pronoun,
-                        "数" => Numeral,
-                        "形" => Adjective(invariant_adjective),
-                        s if s.split_whitespace().collect_tuple() == Some(("形]", "[無変化")) => {
-                            Adjective(true)
-                        }
-                        "動" => Verb,
-                        "副" => Adverb,
-                        "前" => Preposition,
-                        "接" => Conjunction,
-                        "間" => Interjection,
-                        "不定詞マーカー" => InfinitiveMarker,
-                        "冠" => Article,
-                        "不定冠詞" => IndefiniteArticle,
-                        "形式主語" => FormalSubject,
-                        e => unreachable!("Unexpected pos {e:?}"),
-                    }
-                });
-                v.collect()
-            });
+            let pos = pos.map_or_else(Vec::new, |pos| parse_pos_list(pos, invariant_adjective));
 
-            let other_forms = other_forms_regex
+            let other_forms = self
+                .other_forms_regex
                 .captures_iter(other_forms)
                 .map(|res| {
                     let word = res.name("of_word").unwrap().as_str();
                     let pronunciation = res.name("of_pronunciation").unwrap().as_str();
-                    let slahsed = res.name("of_slashed").map_or_else(Vec::new, |pairs| {
+                    let slashed = res.name("of_slashed").map_or_else(Vec::new, |pairs| {
                         let v = pairs.as_str().split('/').skip(1).map(|pair| {
-                            let res = word_and_pronunciation_regex
+                            let res = self
+                                .word_and_pronunciation_regex
                                 .captures(pair.trim())
                                 .expect("Already matched");
                             OtherForm {
-                                word: res.name("wp_word").unwrap().as_str(),
+                                word: res.name("wp_word").unwrap().as_str().into(),
                                 pronunciations: parse_pronuncitation_list(
                                     res.name("wp_pronunciation").unwrap().as_str(),
                                 ),
-                                slahsed: vec![],
+                                slashed: vec![],
                             }
                         });
                         v.collect()
                     });
                     OtherForm {
-                        word,
+                        word: word.into(),
                         pronunciations: parse_pronuncitation_list(pronunciation),
-                        slahsed,
+                        slashed,
                     }
                 })
                 .collect();
 
-            let other_adjective_forms = other_adjective_forms_regex
+            let other_adjective_forms = self
+                .other_adjective_forms_regex
                 .captures_iter(other_adjective_forms)
                 .map(|res| {
                     let word = res.name("oaf_word").unwrap().as_str();
                     let pronunciations = res
                         .name("oaf_pronunciation")
                         .map_or_else(Vec::new, |s| parse_pronuncitation_list(s.as_str()));
-                    let slahsed = res
+                    let slashed = res
                         .name("oaf_slashed")
                         .unwrap()
                         .as_str()
                         .split('/')
                         .skip(1)
                         .map(|s| OtherForm {
-                            word: s.trim(),
+                            word: s.trim().into(),
                             pronunciations: vec![],
-                            slahsed: vec![],
+                            slashed: vec![],
                         })
                         .collect();
                     OtherForm {
-                        word,
+                        word: word.into(),
                         pronunciations,
-                        slahsed,
+                        slashed,
                     }
                 })
                 .collect();
 
-            ret.push(Entry {
-                word,
+            Ok(Some(Entry {
+                word: word.into(),
+                homograph,
                 pos,
                 pronunciations: parse_pronuncitation_list(pronunciation),
                 other_forms,
                 other_adjective_forms,
-            });
+                see_also: self.cross_references(definition)?,
+                definition: definition.into(),
+                section: None,
+            }))
+        } else if let Some(res) = self.redirect_regex.captures(patched) {
+            // The headword cannot contain `→`, so this is the arrow the regex matched
+            let definition = patched[patched.find('→').unwrap()..].trim();
+            Ok(Some(Entry {
+                word: res.name("word").unwrap().as_str().trim().into(),
+                homograph: (res.name("homograph"))
+                    .map(|x| x.as_str().parse())
+                    .transpose()?,
+                pos: (res.name("pos"))
+                    .map_or_else(Vec::new, |pos| parse_pos_list(pos.as_str(), false)),
+                pronunciations: (res.name("pronunciation"))
+                    .map_or_else(Vec::new, |s| parse_pronuncitation_list(s.as_str())),
+                other_forms: vec![],
+                other_adjective_forms: vec![],
+                see_also: self.cross_references(definition)?,
+                definition: definition.into(),
+                section: None,
+            }))
         } else if word.chars().filter(|&c| c == '→').count() == 1 {
             // TODO
+            Ok(None)
         } else {
-            bail!("Could not parse {word:?}")
+            let explanation = self.explain(patched);
+            if explanation.failure.is_none() {
+                // The parts matched one after another are a way for the entry regex to match,
+                // so this means that the two no longer agree
+                bail!(
+                    "Could not parse {word:?}, although every part of the entry grammar matches \
+                     on its own.  The entry regex and its parts disagree"
+                );
+            }
+            bail!("Could not parse {word:?}\n{explanation}")
         }
     }
 
-    Ok(ret)
+    fn cross_references<'a>(&self, definition: &'a str) -> anyhow::Result<Vec<CrossReference<'a>>> {
+        self.cross_reference_regex
+            .captures_iter(definition)
+            .map(|res| {
+                Ok(CrossReference {
+                    word: res.name("xr_word").unwrap().as_str().trim().into(),
+                    homograph: (res.name("xr_homograph"))
+                        .map(|x| x.as_str().parse())
+                        .transpose()?,
+                })
+            })
+            .collect()
+    }
+}
+
+fn parse_pos_list(pos: &str, invariant_adjective: bool) -> Vec<Pos> {
+    use Pos::*;
+    let comma = &[',', '，'];
+    pos.split(comma)
+        .map(|pos| {
+            match pos
+                .trim()
+                .strip_prefix(&['[', '［'])
+                .unwrap()
+                .strip_suffix(&[']', '］'])
+                .unwrap()
+            {
+                "名" => Noun(None),
+                "名・単" => Noun(Some(NounCount::Single)),
+                "名・複" => Noun(Some(NounCount::Multiple)),
+                "固" => ProperNoun,
+                "代" => Pronoun,
+                "数" => Numeral,
+                "形" => Adjective(invariant_adjective),
+                s if s.split_whitespace().collect_tuple() == Some(("形]", "[無変化")) => {
+                    Adjective(true)
+                }
+                "動" => Verb,
+                "副" => Adverb,
+                "前" => Preposition,
+                "接" => Conjunction,
+                "間" => Interjection,
+                "不定詞マーカー" => InfinitiveMarker,
+                "冠" => Article,
+                "不定冠詞" => IndefiniteArticle,
+                "形式主語" => FormalSubject,
+                e => unreachable!("Unexpected pos {e:?}"),
+            }
+        })
+        .collect()
 }
 
-fn parse_pronuncitation_list(s: &str) -> Vec<&str> {
-    s.split(',').map(str::trim).collect()
+fn parse_pronuncitation_list(s: &str) -> Vec<Cow<str>> {
+    s.split(',').map(|s| s.trim().into()).collect()
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+fn into_owned_strs(v: Vec<Cow<str>>) -> Vec<Cow<'static, str>> {
+    v.into_iter().map(|s| s.into_owned().into()).collect()
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Entry<'a> {
-    pub word: &'a str,
+    pub word: Cow<'a, str>,
+    /// The number that tells apart headwords with the same spelling, e.g. `have1` and `have2`.
+    pub homograph: Option<u8>,
     pub pos: Vec<Pos>,
-    pub pronunciations: Vec<&'a str>,
+    pub pronunciations: Vec<Cow<'a, str>>,
     pub other_forms: Vec<OtherForm<'a>>,
     pub other_adjective_forms: Vec<OtherForm<'a>>,
+    /// The Japanese text after the colon.  Missing from version 1 output written before it was
+    /// added.
+    #[serde(default)]
+    pub definition: Cow<'a, str>,
+    /// The entries the definition points at with `→`.  For entries that only redirect to
+    /// another entry, the definition is the `→` and its target.
+    #[serde(default)]
+    pub see_also: Vec<CrossReference<'a>>,
+    /// The letter heading the entry appears under.
+    #[serde(default)]
+    pub section: Option<Cow<'a, str>>,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+/// An [`Entry`] that owns its strings, so that it can outlive the parsed text.
+pub type EntryBuf = Entry<'static>;
+
+impl Entry<'_> {
+    pub fn into_owned(self) -> EntryBuf {
+        Entry {
+            word: self.word.into_owned().into(),
+            homograph: self.homograph,
+            pos: self.pos,
+            pronunciations: into_owned_strs(self.pronunciations),
+            other_forms: (self.other_forms.into_iter())
+                .map(OtherForm::into_owned)
+                .collect(),
+            other_adjective_forms: (self.other_adjective_forms.into_iter())
+                .map(OtherForm::into_owned)
+                .collect(),
+            definition: self.definition.into_owned().into(),
+            see_also: (self.see_also.into_iter())
+                .map(CrossReference::into_owned)
+                .collect(),
+            section: self.section.map(|s| s.into_owned().into()),
+        }
+    }
+
+    /// The distinct spellings of the other forms and their slashed alternatives, without the
+    /// headword.
+    pub fn inflected_forms(&self) -> impl Iterator<Item = &str> {
+        (self.other_forms.iter())
+            .chain(&self.other_adjective_forms)
+            .flat_map(|form| std::iter::once(form).chain(&form.slashed))
+            .map(|form| &*form.word)
+            .filter(move |&form| form != self.word)
+            .unique()
+    }
+
+    /// Splits the definition at the top-level `；`, which separates the senses of the word.
+    pub fn senses(&self) -> Vec<&str> {
+        let mut senses = vec![];
+        let mut depth = 0i32;
+        let mut start = 0;
+        for (i, c) in self.definition.char_indices() {
+            match c {
+                '［' | '〔' | '（' | '(' | '[' | '【' => depth += 1,
+                '］' | '〕' | '）' | ')' | ']' | '】' => depth -= 1,
+                '；' if depth <= 0 => {
+                    senses.push(&self.definition[start..i]);
+                    start = i + c.len_utf8();
+                }
+                _ => {}
+            }
+        }
+        senses.push(&self.definition[start..]);
+        senses
+            .into_iter()
+            .map(|s| s.trim().trim_end_matches('．').trim_end())
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize, JsonSchema)]
 pub enum Pos {
     Noun(Option<NounCount>),
     ProperNoun,
@@ -251,17 +519,48 @@ pub enum Pos {
     IndefiniteArticle,
     FormalSubject,
 }
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize, JsonSchema)]
 pub enum NounCount {
     Single,
     Multiple,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct OtherForm<'a> {
-    pub word: &'a str,
-    pub pronunciations: Vec<&'a str>,
-    pub slahsed: Vec<OtherForm<'a>>,
+    pub word: Cow<'a, str>,
+    pub pronunciations: Vec<Cow<'a, str>>,
+    /// Alternative spellings of the form, written after a `/`.
+    #[serde(alias = "slahsed")]
+    pub slashed: Vec<OtherForm<'a>>,
+}
+
+impl OtherForm<'_> {
+    pub fn into_owned(self) -> OtherForm<'static> {
+        OtherForm {
+            word: self.word.into_owned().into(),
+            pronunciations: into_owned_strs(self.pronunciations),
+            slashed: self
+                .slashed
+                .into_iter()
+                .map(OtherForm::into_owned)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct CrossReference<'a> {
+    pub word: Cow<'a, str>,
+    pub homograph: Option<u8>,
+}
+
+impl CrossReference<'_> {
+    pub fn into_owned(self) -> CrossReference<'static> {
+        CrossReference {
+            word: self.word.into_owned().into(),
+            homograph: self.homograph,
+        }
+    }
 }
 
 pub fn patch(s: &str) -> &str {
@@ -0,0 +1,248 @@
+use std::{collections::HashMap, path::Path};
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{
+    output::{escape_html, form_cell, pos_label, unique_ids},
+    parse_dictionary::{CrossReference, EntryBuf, Pos},
+};
+
+const CSS: &str = "body { font-family: sans-serif; max-width: 50em; margin: auto; padding: 1em; }
+nav a { margin-right: .5em; }
+.entry { margin: .8em 0; }
+.entry:target { background: #ffd; }
+.headword { font-weight: bold; font-size: 1.2em; }
+.pos { display: inline-block; border-radius: .3em; padding: 0 .4em; margin-left: .3em;
+  font-size: .8em; color: white; background: #777; }
+.pos-noun { background: #3b7dd8; }
+.pos-verb { background: #d8573b; }
+.pos-adjective { background: #3ba55c; }
+.pos-adverb { background: #9b59b6; }
+.ipa { color: #555; margin-left: .3em; }
+.forms { color: #555; font-size: .9em; }
+#results li { list-style: none; }
+";
+
+const SEARCH_JS: &str = "const input = document.getElementById('search');
+const results = document.getElementById('results');
+input.addEventListener('input', () => {
+  const query = input.value.trim().toLowerCase();
+  results.innerHTML = '';
+  if (!query) return;
+  const matches = searchIndex.filter(e =>
+    e.word.toLowerCase().startsWith(query) ||
+    e.forms.some(f => f.toLowerCase().startsWith(query)));
+  for (const e of matches.slice(0, 50)) {
+    const li = document.createElement('li');
+    const a = document.createElement('a');
+    a.href = e.url;
+    a.textContent = e.homograph ? e.word + e.homograph : e.word;
+    li.appendChild(a);
+    results.appendChild(li);
+  }
+});
+";
+
+#[derive(Serialize)]
+struct SearchEntry<'a> {
+    word: &'a str,
+    homograph: Option<u8>,
+    forms: Vec<&'a str>,
+    url: String,
+}
+
+/// Writes a static site to `dir`: an index page with the search, one page per letter section
+/// and the search index as `search.json`.  The index is also written as a script, because
+/// browsers don't let pages opened from the file system fetch JSON.
+pub fn render_html(dir: &Path, entries: &[EntryBuf]) -> anyhow::Result<()> {
+    fs_err::create_dir_all(dir)?;
+    fs_err::write(dir.join("style.css"), CSS)?;
+    fs_err::write(dir.join("search.js"), SEARCH_JS)?;
+
+    // Headwords without a homograph number may repeat, the later ones get a suffix
+    let anchors = unique_ids(
+        (entries.iter())
+            .map(|entry| anchor(&entry.word, entry.homograph))
+            .collect(),
+    );
+    let sections = (entries.iter().zip(&anchors))
+        .group_by(|(entry, _)| entry.section.as_deref().unwrap_or("—"))
+        .into_iter()
+        .map(|(title, entries)| (title, entries.collect_vec()))
+        .collect_vec();
+    let page_name = |i: usize| format!("section-{:03}.html", i + 1);
+
+    // Where each headword is, for the cross-references.  References without a homograph number,
+    // or with one that no entry has, go to the first entry with the headword.
+    let mut urls = HashMap::new();
+    for (i, (_, entries)) in sections.iter().enumerate() {
+        for (entry, anchor) in entries {
+            let url = format!("{}#{anchor}", page_name(i));
+            urls.entry((&*entry.word, entry.homograph))
+                .or_insert_with(|| url.clone());
+            urls.entry((&*entry.word, None)).or_insert(url);
+        }
+    }
+
+    let nav = sections
+        .iter()
+        .enumerate()
+        .map(|(i, (title, _))| format!("<a href=\"{}\">{}</a>", page_name(i), escape_html(title)))
+        .join(" ");
+    for (i, (title, entries)) in sections.iter().enumerate() {
+        let body = (entries.iter())
+            .map(|(entry, anchor)| render_entry(entry, anchor, &urls))
+            .join("\n");
+        let html = page(
+            title,
+            &nav,
+            &format!("<h1>{}</h1>\n{body}", escape_html(title)),
+        );
+        fs_err::write(dir.join(page_name(i)), html)?;
+    }
+
+    let search_body = "<input id=\"search\" type=\"search\" placeholder=\"Search\" autofocus>\n\
+                       <ul id=\"results\"></ul>\n\
+                       <script src=\"search-index.js\"></script>\n\
+                       <script src=\"search.js\"></script>";
+    fs_err::write(
+        dir.join("index.html"),
+        page("Danish dictionary", &nav, search_body),
+    )?;
+
+    let search_index = sections
+        .iter()
+        .enumerate()
+        .flat_map(|(i, (_, entries))| {
+            entries.iter().map(move |(entry, anchor)| SearchEntry {
+                word: &entry.word,
+                homograph: entry.homograph,
+                forms: entry.inflected_forms().collect(),
+                url: format!("{}#{anchor}", page_name(i)),
+            })
+        })
+        .collect_vec();
+    let json = serde_json::to_string(&search_index)?;
+    fs_err::write(
+        dir.join("search-index.js"),
+        format!("const searchIndex = {json};\n"),
+    )?;
+    fs_err::write(dir.join("search.json"), json)?;
+    Ok(())
+}
+
+fn page(title: &str, nav: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"da\">
+<head>
+<meta charset=\"utf-8\">
+<title>{}</title>
+<link rel=\"stylesheet\" href=\"style.css\">
+</head>
+<body>
+<nav><a href=\"index.html\">Search</a> {nav}</nav>
+{body}
+</body>
+</html>
+",
+        escape_html(title)
+    )
+}
+
+fn anchor(word: &str, homograph: Option<u8>) -> String {
+    let word = escape_html(&word.replace(char::is_whitespace, "_"));
+    match homograph {
+        Some(homograph) => format!("{word}-{homograph}"),
+        None => word,
+    }
+}
+
+type Urls<'a> = HashMap<(&'a str, Option<u8>), String>;
+
+fn render_entry(entry: &EntryBuf, anchor: &str, urls: &Urls) -> String {
+    let mut html = format!(
+        "<div class=\"entry\" id=\"{anchor}\">\n<span class=\"headword\">{}</span>",
+        escape_html(&entry.word)
+    );
+    if let Some(homograph) = entry.homograph {
+        html += &format!("<sup>{homograph}</sup>");
+    }
+    for &pos in &entry.pos {
+        html += &format!(
+            "<span class=\"pos pos-{}\">{}</span>",
+            pos_class(pos),
+            pos_label(pos)
+        );
+    }
+    if !entry.pronunciations.is_empty() {
+        let ipa = entry
+            .pronunciations
+            .iter()
+            .map(|s| escape_html(s))
+            .join(", ");
+        html += &format!("<span class=\"ipa\">[{ipa}]</span>");
+    }
+    let forms = (entry.other_forms.iter())
+        .chain(&entry.other_adjective_forms)
+        .map(|form| escape_html(&form_cell(form)))
+        .join(", ");
+    if !forms.is_empty() {
+        html += &format!("\n<div class=\"forms\">{forms}</div>");
+    }
+
+    let definition = link_references(&entry.definition, &entry.see_also, urls);
+    html += &format!("\n<div class=\"definition\">{definition}</div>\n</div>");
+    html
+}
+
+/// Escapes the definition and links each `→` reference to its entry.  `see_also` lists the
+/// references in the order they appear, so each is looked for after the previous one.
+fn link_references(definition: &str, see_also: &[CrossReference], urls: &Urls) -> String {
+    let mut html = String::new();
+    let mut rest = definition;
+    let mut references = see_also.iter().peekable();
+    while let (Some(xr), Some(arrow)) = (references.peek(), rest.find('→')) {
+        let (before, after) = rest.split_at(arrow + '→'.len_utf8());
+        html += &escape_html(before);
+        rest = after;
+        let len = match reference_len(after, xr) {
+            Some(len) => len,
+            // An arrow that isn't followed by a headword, as the parser skipped it
+            None => continue,
+        };
+        let (reference, after) = after.split_at(len);
+        let start = reference.len() - reference.trim_start().len();
+        html += &reference[..start];
+        let url = (urls.get(&(&*xr.word, xr.homograph))).or_else(|| urls.get(&(&*xr.word, None)));
+        html += &match url {
+            Some(url) => format!("<a href=\"{url}\">{}</a>", escape_html(&reference[start..])),
+            // Not in the dictionary, or not parsed
+            None => escape_html(&reference[start..]),
+        };
+        rest = after;
+        references.next();
+    }
+    html + &escape_html(rest)
+}
+
+/// The length of the reference `xr` at the start of `text`, including the whitespace before it.
+fn reference_len(text: &str, xr: &CrossReference) -> Option<usize> {
+    let word = text.trim_start().strip_prefix(&*xr.word)?;
+    let rest = match xr.homograph {
+        Some(homograph) => (word.trim_start()).strip_prefix(char::from(b'0' + homograph))?,
+        None => word,
+    };
+    Some(text.len() - rest.len())
+}
+
+fn pos_class(pos: Pos) -> &'static str {
+    match pos {
+        Pos::Noun(_) | Pos::ProperNoun => "noun",
+        Pos::Verb => "verb",
+        Pos::Adjective(_) => "adjective",
+        Pos::Adverb => "adverb",
+        _ => "other",
+    }
+}
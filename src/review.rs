@@ -0,0 +1,394 @@
+use std::{io::Stdout, path::Path};
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use itertools::Itertools;
+use tui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame, Terminal,
+};
+
+use crate::{
+    corrections::{write_corrections, Correction},
+    parse_dictionary::{patch, DictionaryParser, Entry, OtherForm},
+};
+
+/// Why an entry is up for review.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReviewKind {
+    /// The parser rejects the text as extracted.
+    Failed,
+    /// [`patch`] replaces the text before parsing.
+    Patched,
+    /// The corrections file already has a record for the text.
+    Corrected,
+}
+
+impl ReviewKind {
+    fn label(self) -> &'static str {
+        match self {
+            ReviewKind::Failed => "parse failure",
+            ReviewKind::Patched => "patched in the source",
+            ReviewKind::Corrected => "corrected",
+        }
+    }
+}
+
+pub struct ReviewItem {
+    /// The text of the entry as extracted.
+    pub original: String,
+    pub kind: ReviewKind,
+    /// The text being edited, starting from the correction or patch if there is one.
+    text: Vec<char>,
+    /// Position of the cursor in `text`, in chars.
+    cursor: usize,
+}
+
+impl ReviewItem {
+    fn new(original: String, kind: ReviewKind, text: &str) -> Self {
+        let text = text.chars().collect_vec();
+        Self {
+            original,
+            kind,
+            cursor: text.len(),
+            text,
+        }
+    }
+
+    fn text(&self) -> String {
+        self.text.iter().collect()
+    }
+}
+
+/// Picks the entries worth reviewing from the texts of all entries, in document order: the ones
+/// that fail to parse, the ones [`patch`] repairs and the ones with a correction.
+pub fn review_items(
+    texts: impl IntoIterator<Item = String>,
+    corrections: &[Correction],
+) -> anyhow::Result<Vec<ReviewItem>> {
+    // Without the corrections, so that corrected entries can be told apart
+    let parser = DictionaryParser::new()?;
+    let items = texts.into_iter().filter_map(|original| {
+        if let Some(correction) = corrections.iter().find(|c| c.original == original) {
+            return Some(ReviewItem::new(
+                original,
+                ReviewKind::Corrected,
+                &correction.corrected,
+            ));
+        }
+        if parser.parse_entry(&original).is_err() {
+            return Some(ReviewItem::new(
+                original.clone(),
+                ReviewKind::Failed,
+                &original,
+            ));
+        }
+        let patched = patch(&original).to_owned();
+        (patched != original).then(|| ReviewItem::new(original, ReviewKind::Patched, &patched))
+    });
+    Ok(items.collect())
+}
+
+/// Steps through `items` in a terminal UI, re-parsing the edited text on every key.  Saving
+/// writes the edited text as a correction to `corrections_path` right away, so quitting never
+/// loses saved work.
+///
+/// - Left, Right, Home, End, Backspace, Delete and typing edit the text
+/// - PageDown or Ctrl-N and PageUp or Ctrl-P go to the next and previous entry
+/// - Ctrl-S saves the correction; saving the original text removes it
+/// - Ctrl-R resets the text to the original
+/// - Esc or Ctrl-Q quits
+pub fn review(
+    items: Vec<ReviewItem>,
+    mut corrections: Vec<Correction>,
+    corrections_path: &Path,
+) -> anyhow::Result<()> {
+    let parser = DictionaryParser::new()?;
+    let mut state = ReviewState {
+        items,
+        current: 0,
+        status: String::new(),
+    };
+    if state.items.is_empty() {
+        eprintln!("Nothing to review");
+        return Ok(());
+    }
+
+    let mut terminal = TerminalGuard::new()?;
+    loop {
+        terminal
+            .terminal
+            .draw(|frame| draw(frame, &parser, &state, &corrections))?;
+        let key = match event::read()? {
+            Event::Key(key) => key,
+            _ => continue,
+        };
+        state.status.clear();
+        let item = &mut state.items[state.current];
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match (key.code, ctrl) {
+            (KeyCode::Esc, _) | (KeyCode::Char('q'), true) => break,
+            (KeyCode::Char('s'), true) => {
+                let text = item.text();
+                corrections.retain(|c| c.original != item.original);
+                if text != item.original {
+                    corrections.push(Correction {
+                        original: item.original.clone(),
+                        corrected: text,
+                    });
+                }
+                write_corrections(corrections_path, &corrections)?;
+                state.status = format!("Saved to {}", corrections_path.display());
+            }
+            (KeyCode::Char('r'), true) => {
+                *item = ReviewItem::new(item.original.clone(), item.kind, &item.original);
+            }
+            (KeyCode::PageDown, _) | (KeyCode::Char('n'), true) => state.next(),
+            (KeyCode::PageUp, _) | (KeyCode::Char('p'), true) => state.previous(),
+            (KeyCode::Char(c), false) => {
+                item.text.insert(item.cursor, c);
+                item.cursor += 1;
+            }
+            (KeyCode::Backspace, _) if item.cursor > 0 => {
+                item.cursor -= 1;
+                item.text.remove(item.cursor);
+            }
+            (KeyCode::Delete, _) if item.cursor < item.text.len() => {
+                item.text.remove(item.cursor);
+            }
+            (KeyCode::Left, _) => item.cursor = item.cursor.saturating_sub(1),
+            (KeyCode::Right, _) => item.cursor = (item.cursor + 1).min(item.text.len()),
+            (KeyCode::Home, _) => item.cursor = 0,
+            (KeyCode::End, _) => item.cursor = item.text.len(),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+struct ReviewState {
+    items: Vec<ReviewItem>,
+    current: usize,
+    /// Message shown until the next key.
+    status: String,
+}
+
+impl ReviewState {
+    fn next(&mut self) {
+        self.current = (self.current + 1).min(self.items.len() - 1);
+    }
+
+    fn previous(&mut self) {
+        self.current = self.current.saturating_sub(1);
+    }
+}
+
+/// Owns the terminal in raw mode on the alternate screen, and restores it when dropped, also when
+/// the review fails.
+struct TerminalGuard {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl TerminalGuard {
+    fn new() -> anyhow::Result<Self> {
+        enable_raw_mode()?;
+        let mut stdout = std::io::stdout();
+        execute!(stdout, EnterAlternateScreen)?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+        terminal.hide_cursor()?;
+        Ok(Self { terminal })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen);
+        let _ = self.terminal.show_cursor();
+    }
+}
+
+fn draw(
+    frame: &mut Frame<CrosstermBackend<Stdout>>,
+    parser: &DictionaryParser,
+    state: &ReviewState,
+    corrections: &[Correction],
+) {
+    let item = &state.items[state.current];
+    let text = item.text();
+    let saved = corrections
+        .iter()
+        .find(|c| c.original == item.original)
+        .map(|c| c.corrected == text);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Percentage(30),
+            Constraint::Percentage(30),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .split(frame.size());
+
+    let header = format!(
+        "Entry {} of {}: {}{}",
+        state.current + 1,
+        state.items.len(),
+        item.kind.label(),
+        match saved {
+            Some(true) => ", correction saved",
+            Some(false) => ", correction changed",
+            None => "",
+        }
+    );
+    frame.render_widget(
+        Paragraph::new(header).style(Style::default().add_modifier(Modifier::BOLD)),
+        chunks[0],
+    );
+
+    frame.render_widget(
+        Paragraph::new(item.original.as_str())
+            .block(Block::default().borders(Borders::ALL).title("Extracted"))
+            .wrap(Wrap { trim: false }),
+        chunks[1],
+    );
+
+    // The cursor is drawn as a highlighted char, as the terminal cursor can't follow wrapped text
+    let before: String = item.text[..item.cursor].iter().collect();
+    let at = item.text.get(item.cursor).map_or(' ', |&c| c);
+    let after: String = item
+        .text
+        .get(item.cursor + 1..)
+        .unwrap_or(&[])
+        .iter()
+        .collect();
+    let edit = Spans::from(vec![
+        Span::raw(before),
+        Span::styled(
+            at.to_string(),
+            Style::default().add_modifier(Modifier::REVERSED),
+        ),
+        Span::raw(after),
+    ]);
+    frame.render_widget(
+        Paragraph::new(edit)
+            .block(Block::default().borders(Borders::ALL).title("Edit"))
+            .wrap(Wrap { trim: false }),
+        chunks[2],
+    );
+
+    let (result, color) = match parser.parse_entry(&text) {
+        Ok(Some(entry)) => (describe_entry(&entry), Color::Green),
+        Ok(None) => (
+            vec![Spans::from("Recognized, but not supported yet")],
+            Color::Yellow,
+        ),
+        Err(e) => (vec![Spans::from(format!("{e:#}"))], Color::Red),
+    };
+    frame.render_widget(
+        Paragraph::new(result)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Parse result")
+                    .border_style(Style::default().fg(color)),
+            )
+            .wrap(Wrap { trim: false }),
+        chunks[3],
+    );
+
+    let help = if state.status.is_empty() {
+        "PgDn/PgUp next/previous  Ctrl-S save  Ctrl-R reset  Esc quit"
+    } else {
+        &state.status
+    };
+    frame.render_widget(
+        Paragraph::new(help).style(Style::default().fg(Color::DarkGray)),
+        chunks[4],
+    );
+}
+
+fn describe_entry(entry: &Entry) -> Vec<Spans<'static>> {
+    let forms = |forms: &[OtherForm]| {
+        forms
+            .iter()
+            .map(|form| {
+                let slashed = form.slashed.iter().map(|s| format!("/{}", s.word)).join("");
+                format!(
+                    "{} [{}]{slashed}",
+                    form.word,
+                    form.pronunciations.join(", ")
+                )
+            })
+            .join(", ")
+    };
+    let field = |name: &str, value: String| {
+        Spans::from(vec![
+            Span::styled(
+                format!("{name:24}"),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(value),
+        ])
+    };
+    vec![
+        field("headword", entry.word.to_string()),
+        field(
+            "homograph",
+            entry.homograph.map_or_else(String::new, |h| h.to_string()),
+        ),
+        field(
+            "parts of speech",
+            entry.pos.iter().map(|pos| format!("{pos:?}")).join(", "),
+        ),
+        field("pronunciations", entry.pronunciations.join(", ")),
+        field("other forms", forms(&entry.other_forms)),
+        field("other adjective forms", forms(&entry.other_adjective_forms)),
+        field("definition", entry.definition.to_string()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_failed_patched_and_corrected_entries() {
+        let amager =
+            "Amager [固] [ˈαˌmα;] アマー［地名：コペンハーゲン南部の島．Kastrup空港がある］． ";
+        let texts = [
+            "knuse [動] [ˈknu:sə]: 壊す． ",
+            "knuse [動] [ˈknu:sə] 壊す． ",
+            amager,
+            "lille [形] [ˈlilə] 小さな． ",
+        ];
+        let corrections = [Correction {
+            original: "lille [形] [ˈlilə] 小さな． ".to_owned(),
+            corrected: "lille [形] [ˈlilə]: 小さな． ".to_owned(),
+        }];
+        let items = review_items(texts.map(str::to_owned), &corrections).unwrap();
+        let items = (items.iter())
+            .map(|item| (item.original.as_str(), item.kind, item.text()))
+            .collect_vec();
+        assert_eq!(
+            items,
+            [
+                (texts[1], ReviewKind::Failed, texts[1].to_owned()),
+                (amager, ReviewKind::Patched, patch(amager).to_owned()),
+                (
+                    texts[3],
+                    ReviewKind::Corrected,
+                    corrections[0].corrected.clone()
+                ),
+            ]
+        );
+    }
+}
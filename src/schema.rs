@@ -0,0 +1,113 @@
+use std::path::Path;
+
+use anyhow::bail;
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::parse_dictionary::{Entry, EntryBuf};
+
+/// Version of the shape of the JSON and JSON Lines output.  Bump it whenever the serialized shape
+/// of [`Entry`] changes, and regenerate the published schema with the `schema` subcommand.
+///
+/// Version 1 is the unversioned output, which had `slahsed` instead of `slashed`.  `definition`
+/// was added during version 1, so older version 1 files lack it; every version 2 file has it.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// The whole output of [`crate::output::Format::Json`].
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Document<'a> {
+    pub schema_version: u32,
+    pub entries: Vec<Entry<'a>>,
+}
+
+/// A line of [`crate::output::Format::Jsonl`].
+#[derive(Serialize)]
+pub struct Record<'e, 'a> {
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub entry: &'e Entry<'a>,
+}
+
+/// The JSON Schema of [`Document`], with the version it describes under `x-schema-version`.
+pub fn json_schema() -> RootSchema {
+    let mut schema = schema_for!(Document<'static>);
+    schema
+        .schema
+        .extensions
+        .insert("x-schema-version".into(), SCHEMA_VERSION.into());
+    schema
+}
+
+/// Fails if the published schema at `path` differs from the shape the code serializes, which
+/// means that either the shape changed without bumping [`SCHEMA_VERSION`] or the published
+/// schema wasn't regenerated after the bump.
+pub fn check_schema(path: &Path) -> anyhow::Result<()> {
+    let published: Value = serde_json::from_str(&fs_err::read_to_string(path)?)?;
+    let current = serde_json::to_value(json_schema())?;
+    if published == current {
+        return Ok(());
+    }
+    let published_version = published.get("x-schema-version").and_then(Value::as_u64);
+    if published_version == Some(SCHEMA_VERSION.into()) {
+        bail!(
+            "The serialized shape of the entries changed, but SCHEMA_VERSION is still \
+             {SCHEMA_VERSION}. Bump it and regenerate {path:?}"
+        );
+    }
+    bail!(
+        "{path:?} describes schema version {published_version:?}, but the code writes version \
+         {SCHEMA_VERSION}. Regenerate it with the schema subcommand"
+    )
+}
+
+/// Checks the version of entries read back from a file.  Files without a version were written
+/// before versioning, which is version 1.
+pub fn check_version(version: Option<u32>) -> anyhow::Result<()> {
+    let version = version.unwrap_or(1);
+    if version > SCHEMA_VERSION {
+        bail!("The entries have schema version {version}, this build reads up to {SCHEMA_VERSION}");
+    }
+    Ok(())
+}
+
+/// An entry together with the version of the line it was read from.
+#[derive(Deserialize)]
+pub(crate) struct VersionedEntry {
+    pub schema_version: Option<u32>,
+    #[serde(flatten)]
+    pub entry: EntryBuf,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBLISHED: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/schema/entries.schema.json");
+
+    /// Fails when the serialized shape changes without regenerating the published schema, and
+    /// when the published schema describes another version.
+    #[test]
+    fn published_schema_matches_serialized_shape() {
+        let published: Value =
+            serde_json::from_str(&fs_err::read_to_string(PUBLISHED).unwrap()).unwrap();
+        assert_eq!(
+            published.get("x-schema-version").and_then(Value::as_u64),
+            Some(SCHEMA_VERSION.into()),
+            "The published schema describes another version, regenerate it"
+        );
+        assert_eq!(
+            published,
+            serde_json::to_value(json_schema()).unwrap(),
+            "The serialized shape changed: bump SCHEMA_VERSION and regenerate {PUBLISHED}"
+        );
+        check_schema(Path::new(PUBLISHED)).unwrap();
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        check_version(None).unwrap();
+        check_version(Some(SCHEMA_VERSION)).unwrap();
+        assert!(check_version(Some(SCHEMA_VERSION + 1)).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use anyhow::anyhow;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{
+    inflection::Slot,
+    lemmatize::lemmatize,
+    lookup::{fold, Dictionary},
+};
+
+/// Number of entries returned by a prefix search without a `limit`.
+const DEFAULT_PREFIX_LIMIT: usize = 20;
+
+/// Serves lookups in `dictionary` as JSON over HTTP at `address` until the process is stopped.
+///
+/// - `GET /headword?q=knuse`: the entries with the headword, ignoring case, accents and the
+///   spelling of `å`, `æ` and `ø` if there's no exact match
+/// - `GET /form?q=knuste`: the entries the word is a form of, with the slot of the form
+/// - `GET /prefix?q=knu&limit=20`: the entries whose headword starts with the prefix
+/// - `GET /reverse?q=砕く`: the entries with a Japanese gloss, most direct match first
+///
+/// Every response allows any origin, so the API can be used from pages served elsewhere.
+pub fn serve(dictionary: &Dictionary, address: &str) -> anyhow::Result<()> {
+    let server = Server::http(address).map_err(|e| anyhow!("Cannot listen on {address}: {e}"))?;
+    eprintln!("Listening on http://{}", server.server_addr());
+    serve_requests(dictionary, &server);
+    Ok(())
+}
+
+/// Answers the requests to `server` until [`Server::unblock`] is called.
+pub fn serve_requests(dictionary: &Dictionary, server: &Server) {
+    for request in server.incoming_requests() {
+        let (status, body) = match handle(dictionary, &request) {
+            Ok(body) => (200, body),
+            Err((status, message)) => (status, json!({ "error": message })),
+        };
+        // A client that went away shouldn't stop the server
+        if let Err(e) = respond(request, status, &body) {
+            eprintln!("Warning: cannot respond: {e}");
+        }
+    }
+}
+
+type HandlerResult = Result<Value, (u16, String)>;
+
+fn handle(dictionary: &Dictionary, request: &Request) -> HandlerResult {
+    match request.method() {
+        Method::Get => {}
+        // CORS preflight
+        Method::Options => return Ok(Value::Null),
+        method => return Err((405, format!("Method {method} not allowed"))),
+    }
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let params = parse_query(query);
+    let param = |name: &str| {
+        (params.iter())
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let q = || param("q").ok_or_else(|| (400, "Missing query parameter q".to_owned()));
+    match path {
+        "/headword" => {
+            let q = q()?;
+            let entries = (dictionary.lookup(q).into_iter())
+                .filter(|(entry, _)| fold(&entry.word) == fold(q))
+                .map(|(entry, kind)| json!({ "entry": entry, "match": kind }))
+                .collect();
+            Ok(Value::Array(entries))
+        }
+        "/form" => {
+            let entries = (lemmatize(dictionary, q()?).into_iter())
+                .map(|candidate| {
+                    json!({
+                        "entry": candidate.entry,
+                        "headword": candidate.headword,
+                        "slot": candidate.slot.map(Slot::label),
+                        "tags": candidate.slot.map_or(&[][..], Slot::tags),
+                    })
+                })
+                .collect();
+            Ok(Value::Array(entries))
+        }
+        "/prefix" => {
+            let limit = match param("limit") {
+                Some(limit) => limit
+                    .parse()
+                    .map_err(|_| (400, format!("Invalid limit {limit:?}")))?,
+                None => DEFAULT_PREFIX_LIMIT,
+            };
+            Ok(json!(dictionary.with_prefix(q()?, limit)))
+        }
+        "/reverse" => {
+            let entries = (dictionary.reverse_lookup(q()?).into_iter())
+                .map(|(entry, gloss, kind)| {
+                    json!({ "entry": entry, "gloss": gloss.text, "match": kind })
+                })
+                .collect();
+            Ok(Value::Array(entries))
+        }
+        _ => Err((404, format!("No endpoint at {path}"))),
+    }
+}
+
+fn respond(request: Request, status: u16, body: &Value) -> anyhow::Result<()> {
+    let headers = [
+        ("Content-Type", "application/json; charset=utf-8"),
+        ("Access-Control-Allow-Origin", "*"),
+        ("Access-Control-Allow-Methods", "GET, OPTIONS"),
+        ("Access-Control-Allow-Headers", "Content-Type"),
+    ];
+    let mut response = if request.method() == &Method::Options {
+        Response::from_string("").with_status_code(204)
+    } else {
+        Response::from_string(serde_json::to_string(body)?).with_status_code(status)
+    };
+    for (name, value) in headers {
+        let header = Header::from_bytes(name, value).expect("Valid header");
+        response.add_header(header);
+    }
+    request.respond(response)?;
+    Ok(())
+}
+
+/// Splits a query string into its decoded keys and values.
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    (query.split('&'))
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> String {
+    let mut bytes = vec![];
+    let mut rest = s.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        rest = tail;
+        match b {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = rest.get(..2).and_then(|hex| std::str::from_utf8(hex).ok());
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(decoded) => {
+                        bytes.push(decoded);
+                        rest = &rest[2..];
+                    }
+                    None => bytes.push(b'%'),
+                }
+            }
+            b => bytes.push(b),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
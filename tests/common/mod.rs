@@ -0,0 +1,29 @@
+// Each test crate uses only some of the helpers
+#![allow(dead_code)]
+
+use danish_dictionary_parser::{
+    layout::Word,
+    parse_dictionary::{parse_entries, EntryBuf},
+};
+
+/// The text of each entry in `fixtures/entries.txt`, as written by the extract subcommand.
+pub fn fixture_texts() -> Vec<String> {
+    include_str!("../fixtures/entries.txt")
+        .lines()
+        .map(str::to_owned)
+        .collect()
+}
+
+/// The entries of `fixtures/entries.txt`, parsed.
+pub fn fixture_entries() -> Vec<EntryBuf> {
+    let words = fixture_texts().into_iter().map(|text| {
+        Ok(Word {
+            text,
+            section: None,
+        })
+    });
+    parse_entries(words, &[])
+        .unwrap()
+        .collect::<anyhow::Result<_>>()
+        .unwrap()
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use std::io::Read;
+
+use danish_dictionary_parser::export_anki::{export_anki, NoteType};
+use rusqlite::Connection;
+
+#[test]
+fn notes_have_distinct_guids_and_cards_are_numbered() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("entries.apkg");
+    let note_types = [NoteType::DaJa, NoteType::JaDa, NoteType::Inflection];
+    export_anki(&path, "Dansk", &note_types, &common::fixture_entries()).unwrap();
+
+    let mut zip = zip::ZipArchive::new(fs_err::File::open(&path).unwrap()).unwrap();
+    let mut collection = vec![];
+    (zip.by_name("collection.anki2").unwrap())
+        .read_to_end(&mut collection)
+        .unwrap();
+    let collection_path = dir.path().join("collection.anki2");
+    fs_err::write(&collection_path, collection).unwrap();
+    let conn = Connection::open(collection_path).unwrap();
+
+    let query = |sql: &str| -> i64 { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+    let notes = query("SELECT count(*) FROM notes");
+    assert_eq!(query("SELECT count(DISTINCT guid) FROM notes"), notes);
+    // Both entries of bil have a note of each direction, and the first one three inflections
+    let bil = "SELECT count(*) FROM notes WHERE flds LIKE 'bil' || char(31) || '%'";
+    assert_eq!(query(bil), 7);
+    // sommer has two indefinite plurals, somre and somrer
+    let plurals = "SELECT count(*) FROM notes \
+                   WHERE flds LIKE 'sommer' || char(31) || '%indefinite plural%'";
+    assert_eq!(query(plurals), 2);
+
+    // New cards are shown in the order of `due`
+    let mut statement = conn.prepare("SELECT due FROM cards ORDER BY id").unwrap();
+    let due: Vec<i64> = statement
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(due, (1..=notes).collect::<Vec<_>>());
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use std::io::Read;
+
+use danish_dictionary_parser::{
+    export_epub::export_epub, layout::Word, parse_dictionary::parse_entries,
+};
+use regex::Regex;
+use zip::{CompressionMethod, ZipArchive};
+
+fn read(zip: &mut ZipArchive<fs_err::File>, name: &str) -> String {
+    let mut text = String::new();
+    zip.by_name(name)
+        .unwrap()
+        .read_to_string(&mut text)
+        .unwrap();
+    text
+}
+
+#[test]
+fn package_lists_every_section_in_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("dansk.epub");
+    // The fixture under two letter headings
+    let words = (common::fixture_texts().into_iter().enumerate()).map(|(i, text)| {
+        let section = if i < 4 { "H" } else { "K" };
+        Ok(Word {
+            text,
+            section: Some(section.to_owned()),
+        })
+    });
+    export_epub(&path, parse_entries(words, &[]).unwrap()).unwrap();
+
+    let mut zip = ZipArchive::new(fs_err::File::open(&path).unwrap()).unwrap();
+    let opf = read(&mut zip, "OEBPS/content.opf");
+    let nav = read(&mut zip, "OEBPS/nav.xhtml");
+    let first = read(&mut zip, "OEBPS/section_001.xhtml");
+    let second = read(&mut zip, "OEBPS/section_002.xhtml");
+
+    let mimetype = zip.by_index(0).unwrap();
+    assert_eq!(mimetype.name(), "mimetype");
+    assert_eq!(mimetype.compression(), CompressionMethod::Stored);
+    drop(mimetype);
+    assert_eq!(read(&mut zip, "mimetype"), "application/epub+zip");
+
+    let item_regex =
+        Regex::new(r#"<item id="([^"]+)" href="([^"]+)" media-type="([^"]+)""#).unwrap();
+    let items: Vec<_> = (item_regex.captures_iter(&opf))
+        .map(|c| (c[1].to_owned(), c[2].to_owned(), c[3].to_owned()))
+        .collect();
+    let spine: Vec<_> = (Regex::new(r#"<itemref idref="([^"]+)"/>"#).unwrap())
+        .captures_iter(&opf)
+        .map(|c| c[1].to_owned())
+        .collect();
+    let names: Vec<_> = zip.file_names().map(str::to_owned).collect();
+    for (id, href, media_type) in &items {
+        assert!(names.contains(&format!("OEBPS/{href}")), "No file {href}");
+        if media_type == "application/xhtml+xml" && id != "nav" {
+            assert!(spine.contains(id), "{id} isn't in the spine");
+        }
+    }
+    let spine_files: Vec<_> = (spine.iter())
+        .map(|id| &items.iter().find(|(i, _, _)| i == id).unwrap().1)
+        .collect();
+    assert_eq!(spine_files, ["section_001.xhtml", "section_002.xhtml"]);
+    assert!(!names.contains(&"OEBPS/section_003.xhtml".to_owned()));
+
+    assert!(nav.contains(r#"<a href="section_001.xhtml">H</a>"#));
+    assert!(nav.contains(r#"<a href="section_002.xhtml">K</a>"#));
+    assert!(first.contains("<h1>H</h1>") && first.contains(r#"<idx:orth value="have">"#));
+    assert!(second.contains("<h1>K</h1>") && second.contains(r#"<idx:orth value="bil">"#));
+    assert!(!first.contains(r#"<idx:orth value="bil">"#));
+
+    let forms = (Regex::new(r#"<idx:iform value="([^"]+)"/>"#).unwrap())
+        .captures_iter(&first)
+        .map(|c| c[1].to_owned())
+        .collect::<Vec<_>>();
+    for form in ["haverne", "knuste", "knus"] {
+        assert!(forms.iter().any(|f| f == form), "No form {form}");
+    }
+}
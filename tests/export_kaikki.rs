@@ -0,0 +1,119 @@
+mod common;
+
+use danish_dictionary_parser::{
+    export_kaikki::kaikki_records,
+    inflection::{slots, Slot},
+    parse_dictionary::EntryBuf,
+};
+use serde_json::json;
+
+fn entry<'a>(entries: &'a [EntryBuf], word: &str) -> &'a EntryBuf {
+    (entries.iter())
+        .find(|entry| entry.word == word)
+        .unwrap_or_else(|| panic!("No entry {word}"))
+}
+
+/// The forms of the entry with their slots.
+fn form_slots(entry: &EntryBuf) -> Vec<(String, Option<Slot>)> {
+    (slots(entry).into_iter())
+        .map(|(form, slot)| (form.word.to_string(), slot))
+        .collect()
+}
+
+fn expected(forms: &[(&str, Slot)]) -> Vec<(String, Option<Slot>)> {
+    (forms.iter())
+        .map(|&(form, slot)| (form.to_owned(), Some(slot)))
+        .collect()
+}
+
+#[test]
+fn places_forms_in_their_paradigm() {
+    let entries = common::fixture_entries();
+    assert_eq!(
+        form_slots(entry(&entries, "have")),
+        expected(&[
+            ("haven", Slot::DefiniteSingular),
+            ("haver", Slot::IndefinitePlural),
+            ("haverne", Slot::DefinitePlural),
+        ])
+    );
+    // Not three forms, so placed by their endings
+    assert_eq!(
+        form_slots(entry(&entries, "sommer")),
+        expected(&[
+            ("sommeren", Slot::DefiniteSingular),
+            ("somre", Slot::IndefinitePlural),
+            ("somrene", Slot::DefinitePlural),
+            ("somrer", Slot::IndefinitePlural),
+        ])
+    );
+    assert_eq!(
+        form_slots(entry(&entries, "knuse")),
+        expected(&[
+            ("knuser", Slot::Present),
+            ("knuste", Slot::Past),
+            ("knust", Slot::PastParticiple),
+            ("knusende", Slot::PresentParticiple),
+            ("knus", Slot::Imperative),
+        ])
+    );
+    assert_eq!(
+        form_slots(entry(&entries, "varm")),
+        expected(&[
+            ("varmt", Slot::Neuter),
+            ("varme", Slot::PluralDefinite),
+            ("varmere", Slot::Comparative),
+            ("varmest", Slot::Superlative),
+            ("varmeste", Slot::DefiniteSuperlative),
+        ])
+    );
+    // lille has no neuter
+    assert_eq!(
+        form_slots(entry(&entries, "lille")),
+        expected(&[
+            ("små", Slot::PluralDefinite),
+            ("mindre", Slot::Comparative),
+            ("mindst", Slot::Superlative),
+            ("mindste", Slot::DefiniteSuperlative),
+        ])
+    );
+    assert_eq!(
+        form_slots(entry(&entries, "spændende")),
+        expected(&[
+            ("mere spændende", Slot::Comparative),
+            ("mest spændende", Slot::Superlative),
+        ])
+    );
+}
+
+#[test]
+fn writes_wiktextract_records() {
+    let entries = common::fixture_entries();
+    let records = kaikki_records(entry(&entries, "øre"));
+    assert_eq!(records.len(), 1);
+    let record = &records[0];
+    assert_eq!(record["word"], "øre");
+    assert_eq!(record["lang"], "Danish");
+    assert_eq!(record["lang_code"], "da");
+    assert_eq!(record["pos"], "noun");
+    assert_eq!(record["etymology_number"], 1);
+    assert_eq!(record["sounds"], json!([{ "ipa": "[ˈø:ɔ]" }]));
+    // The slashed alternative øren gets the tags of ører
+    assert_eq!(
+        record["forms"],
+        json!([
+            { "form": "øret", "tags": ["definite", "singular"] },
+            { "form": "ører", "tags": ["indefinite", "plural"] },
+            { "form": "øren", "tags": ["indefinite", "plural"] },
+            { "form": "ørerne", "tags": ["definite", "plural"] },
+        ])
+    );
+    assert_eq!(record["senses"], json!([{ "glosses": ["耳"] }]));
+    assert!(record.get("related").is_none());
+    assert_eq!(record.as_object().unwrap().len(), 8);
+
+    assert_eq!(
+        kaikki_records(entry(&entries, "haven")),
+        [json!({ "title": "haven", "redirect": "have" })]
+    );
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use danish_dictionary_parser::export_sqlite::export_sqlite;
+use rusqlite::Connection;
+
+fn search(conn: &Connection, table: &str, query: &str) -> Vec<String> {
+    let mut statement = conn
+        .prepare(&format!(
+            "SELECT word FROM {table} JOIN entries ON entries.id = {table}.rowid \
+             WHERE {table} MATCH ?1 ORDER BY entries.id"
+        ))
+        .unwrap();
+    let words = statement
+        .query_map([query], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    words
+}
+
+#[test]
+fn searches_inside_japanese_definitions() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("entries.sqlite");
+    export_sqlite(&path, common::fixture_entries().into_iter().map(Ok)).unwrap();
+    let conn = Connection::open(path).unwrap();
+
+    // Words in the middle of a definition, which has no spaces to split it at
+    assert_eq!(search(&conn, "search_definitions", "自動車"), ["bil"]);
+    assert_eq!(search(&conn, "search_definitions", "思いやり"), ["varm"]);
+    assert_eq!(search(&conn, "search_definitions", "自家用車"), ["bil"]);
+    // Inflected forms are still found by word
+    assert_eq!(search(&conn, "search", "haverne"), ["have"]);
+    assert_eq!(search(&conn, "search", "idéerne"), ["idé"]);
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use std::cmp::Ordering;
+
+use danish_dictionary_parser::export_stardict::export_stardict;
+
+/// StarDict's order: ASCII case-insensitive, ties broken by the raw bytes.
+fn stardict_cmp(a: &str, b: &str) -> Ordering {
+    let fold = |s: &str| s.to_ascii_lowercase().into_bytes();
+    fold(a).cmp(&fold(b)).then_with(|| a.cmp(b))
+}
+
+/// Splits `.idx` or `.syn` records into their word and the `fields` big-endian numbers after it.
+fn records(mut data: &[u8], fields: usize) -> Vec<(String, Vec<u32>)> {
+    let mut records = vec![];
+    while !data.is_empty() {
+        let end = data
+            .iter()
+            .position(|&b| b == 0)
+            .expect("NUL after the word");
+        let word = String::from_utf8(data[..end].to_vec()).unwrap();
+        data = &data[end + 1..];
+        let numbers = (0..fields)
+            .map(|i| u32::from_be_bytes(data[4 * i..4 * i + 4].try_into().unwrap()))
+            .collect();
+        data = &data[4 * fields..];
+        records.push((word, numbers));
+    }
+    records
+}
+
+#[test]
+fn index_and_synonyms_point_at_the_articles() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("dansk");
+    let entries = common::fixture_entries();
+    export_stardict(&path, common::fixture_entries().into_iter().map(Ok)).unwrap();
+
+    let dict = fs_err::read(path.with_extension("dict")).unwrap();
+    let idx = fs_err::read(path.with_extension("idx")).unwrap();
+    let syn = fs_err::read(path.with_extension("syn")).unwrap();
+    let ifo = fs_err::read_to_string(path.with_extension("ifo")).unwrap();
+
+    let articles = records(&idx, 2);
+    assert_eq!(articles.len(), entries.len());
+    for pair in articles.windows(2) {
+        assert_ne!(stardict_cmp(&pair[0].0, &pair[1].0), Ordering::Greater);
+    }
+    let article = |i: usize| {
+        let (_, location) = &articles[i];
+        let (offset, size) = (location[0] as usize, location[1] as usize);
+        std::str::from_utf8(&dict[offset..offset + size]).unwrap()
+    };
+    for (i, (word, _)) in articles.iter().enumerate() {
+        assert!(article(i).starts_with(&format!("<b>{word}</b>")));
+    }
+    let total = articles.iter().map(|(_, l)| l[1] as usize).sum::<usize>();
+    assert_eq!(total, dict.len());
+
+    let synonyms = records(&syn, 1);
+    let forms = entries
+        .iter()
+        .map(|e| e.inflected_forms().count())
+        .sum::<usize>();
+    assert_eq!(synonyms.len(), forms);
+    for pair in synonyms.windows(2) {
+        assert_ne!(stardict_cmp(&pair[0].0, &pair[1].0), Ordering::Greater);
+    }
+    let synonym = |form: &str| {
+        let (_, index) = (synonyms.iter())
+            .find(|(word, _)| word == form)
+            .unwrap_or_else(|| panic!("No synonym {form}"));
+        index[0] as usize
+    };
+    let haverne = synonym("haverne");
+    assert_eq!(articles[haverne].0, "have");
+    assert!(article(haverne).contains("庭"));
+    assert_eq!(articles[synonym("knuste")].0, "knuse");
+
+    assert!(ifo.contains(&format!("wordcount={}\n", entries.len())));
+    assert!(ifo.contains(&format!("synwordcount={forms}\n")));
+    assert!(ifo.contains(&format!("idxfilesize={}\n", idx.len())));
+}
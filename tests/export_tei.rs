@@ -0,0 +1,60 @@
+mod common;
+
+use std::{collections::HashSet, path::Path, process::Command};
+
+use danish_dictionary_parser::export_tei::export_tei;
+use regex::Regex;
+
+fn export_fixture(dir: &Path) -> String {
+    let path = dir.join("entries.xml");
+    export_tei(&path, common::fixture_entries().into_iter().map(Ok)).unwrap();
+    fs_err::read_to_string(path).unwrap()
+}
+
+#[test]
+fn references_point_at_written_ids() {
+    let dir = tempfile::tempdir().unwrap();
+    let xml = export_fixture(dir.path());
+
+    let ids: HashSet<&str> = (Regex::new(r#"xml:id="([^"]+)""#).unwrap())
+        .captures_iter(&xml)
+        .map(|c| c.get(1).unwrap().as_str())
+        .collect();
+    let targets: Vec<&str> = (Regex::new(r##"target="#([^"]+)""##).unwrap())
+        .captures_iter(&xml)
+        .map(|c| c.get(1).unwrap().as_str())
+        .collect();
+    for target in &targets {
+        assert!(ids.contains(target), "No entry with id {target}");
+    }
+
+    // The repeated headword gets a suffix, references without a homograph number point at the
+    // first entry and references to words that aren't in the dictionary have no target
+    assert!(ids.contains("da.bil") && ids.contains("da.bil-2"));
+    assert_eq!(targets, ["da.have.1", "da.øre.1", "da.bil"]);
+    assert!(xml.contains(r#"<ref type="entry">vogn</ref>"#));
+}
+
+/// Validates against `fixtures/lex0.rng`, the RelaxNG schema published with TEI Lex-0, or the
+/// schema at `TEI_LEX0_RNG`.
+#[test]
+#[ignore = "needs xmllint and the published TEI Lex-0 schema vendored at tests/fixtures/lex0.rng"]
+fn validates_against_tei_lex0() {
+    let dir = tempfile::tempdir().unwrap();
+    export_fixture(dir.path());
+
+    let schema = std::env::var("TEI_LEX0_RNG").unwrap_or_else(|_| {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/lex0.rng").to_owned()
+    });
+    assert!(Path::new(&schema).exists(), "No TEI Lex-0 schema at {schema}");
+    let output = Command::new("xmllint")
+        .args(["--noout", "--relaxng", &schema])
+        .arg(dir.path().join("entries.xml"))
+        .output()
+        .expect("Cannot run xmllint");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use std::io::Read;
+
+use danish_dictionary_parser::export_yomitan::export_yomitan;
+use serde_json::{json, Value};
+
+#[test]
+fn archive_has_index_tags_and_terms() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("dansk.zip");
+    export_yomitan(&path, common::fixture_entries().into_iter().map(Ok)).unwrap();
+
+    let mut zip = zip::ZipArchive::new(fs_err::File::open(&path).unwrap()).unwrap();
+    let mut read_json = |name: &str| -> Value {
+        let mut json = String::new();
+        zip.by_name(name)
+            .unwrap()
+            .read_to_string(&mut json)
+            .unwrap();
+        serde_json::from_str(&json).unwrap()
+    };
+    let index = read_json("index.json");
+    assert_eq!(index["title"], "dansk");
+    assert_eq!(index["format"], 3);
+    assert_eq!(index["sequenced"], true);
+    assert_eq!(index["sourceLanguage"], "da");
+    assert_eq!(index["targetLanguage"], "ja");
+
+    let tags = read_json("tag_bank_1.json");
+    assert!(tags
+        .as_array()
+        .unwrap()
+        .contains(&json!(["v", "partOfSpeech", 0, "verb", 0])));
+
+    let terms = read_json("term_bank_1.json");
+    let terms = terms.as_array().unwrap();
+    let term = |expression: &str| -> Vec<&Value> {
+        (terms.iter())
+            .filter(|term| term[0] == expression)
+            .collect()
+    };
+    let knuse = term("knuse");
+    assert_eq!(knuse.len(), 1);
+    let knuse = knuse[0];
+    assert_eq!(knuse.as_array().unwrap().len(), 8);
+    assert_eq!(knuse[1], "");
+    assert_eq!(knuse[2], "v");
+    assert_eq!(knuse[4], 0);
+    let content = &knuse[5][0];
+    assert_eq!(content["type"], "structured-content");
+    assert_eq!(
+        content["content"][0],
+        json!({
+            "tag": "div",
+            "data": { "content": "pronunciation" },
+            "content": "[ˈknu:sə]",
+        })
+    );
+    assert_eq!(
+        content["content"][1]["content"],
+        "壊す，こなごなにする，砕く"
+    );
+
+    // The inflected form deinflects to the headword and shares its sequence, so Yomitan shows
+    // them as one result
+    let knuste = term("knuste");
+    assert_eq!(knuste.len(), 1);
+    assert_eq!(knuste[0][5], json!([["knuse", []]]));
+    assert_eq!(knuste[0][6], knuse[6]);
+
+    // The two entries of bil stay apart
+    let bil = term("bil");
+    assert_eq!(bil.len(), 2);
+    assert_ne!(bil[0][6], bil[1][6]);
+}
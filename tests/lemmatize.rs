@@ -0,0 +1,85 @@
+mod common;
+
+use danish_dictionary_parser::{inflection::Slot, lemmatize::lemmatize, lookup::Dictionary};
+
+/// The headword, homograph, whether the form is the headword and slot of each candidate.
+fn lemmas(dictionary: &Dictionary, form: &str) -> Vec<(String, Option<u8>, bool, Option<Slot>)> {
+    (lemmatize(dictionary, form).into_iter())
+        .map(|c| {
+            (
+                c.entry.word.to_string(),
+                c.entry.homograph,
+                c.headword,
+                c.slot,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn maps_inflected_forms_to_their_entries() {
+    let dictionary = Dictionary::new(common::fixture_entries());
+    let lemmas = |form| lemmas(&dictionary, form);
+
+    assert_eq!(
+        lemmas("knuste"),
+        [("knuse".to_owned(), None, false, Some(Slot::Past))]
+    );
+    assert_eq!(
+        lemmas("haverne"),
+        [(
+            "have".to_owned(),
+            Some(1),
+            false,
+            Some(Slot::DefinitePlural)
+        )]
+    );
+    assert_eq!(
+        lemmas("mindst"),
+        [("lille".to_owned(), None, false, Some(Slot::Superlative))]
+    );
+    // A slashed alternative shares the slot of the form it follows
+    assert_eq!(
+        lemmas("øren"),
+        [(
+            "øre".to_owned(),
+            Some(1),
+            false,
+            Some(Slot::IndefinitePlural)
+        )]
+    );
+    assert_eq!(lemmas("knuse"), [("knuse".to_owned(), None, true, None)]);
+}
+
+#[test]
+fn gives_every_candidate_of_an_ambiguous_form() {
+    let dictionary = Dictionary::new(common::fixture_entries());
+    // haven is both a form of have1 and the headword of a cross-reference entry
+    assert_eq!(
+        lemmas(&dictionary, "haven"),
+        [
+            (
+                "have".to_owned(),
+                Some(1),
+                false,
+                Some(Slot::DefiniteSingular)
+            ),
+            ("haven".to_owned(), None, true, None),
+        ]
+    );
+}
+
+#[test]
+fn looks_up_capitalized_forms_in_lower_case() {
+    let dictionary = Dictionary::new(common::fixture_entries());
+    assert_eq!(
+        lemmas(&dictionary, "Knuste"),
+        [("knuse".to_owned(), None, false, Some(Slot::Past))]
+    );
+    // Capitalized headwords are found as they are
+    assert_eq!(
+        lemmas(&dictionary, "Amager"),
+        [("Amager".to_owned(), None, true, None)]
+    );
+    assert!(lemmas(&dictionary, "amager").is_empty());
+}
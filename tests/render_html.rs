@@ -0,0 +1,52 @@
+mod common;
+
+use std::collections::HashMap;
+
+use danish_dictionary_parser::render_html::render_html;
+use regex::Regex;
+
+#[test]
+fn links_point_at_unique_anchors() {
+    let dir = tempfile::tempdir().unwrap();
+    render_html(dir.path(), &common::fixture_entries()).unwrap();
+
+    let id_regex = Regex::new(r#"<div class="entry" id="([^"]+)">"#).unwrap();
+    let link_regex = Regex::new(r##"<a href="([^"#]+)#([^"]+)">([^<]*)</a>"##).unwrap();
+    // The page of each anchor
+    let mut anchors = HashMap::new();
+    let mut links = vec![];
+    let mut html = String::new();
+    for file in fs_err::read_dir(dir.path()).unwrap() {
+        let file = file.unwrap();
+        let name = file.file_name().into_string().unwrap();
+        if !name.starts_with("section-") {
+            continue;
+        }
+        let page = fs_err::read_to_string(file.path()).unwrap();
+        for c in id_regex.captures_iter(&page) {
+            let previous = anchors.insert(c[1].to_owned(), name.clone());
+            assert!(previous.is_none(), "Repeated {}", &c[1]);
+        }
+        for c in link_regex.captures_iter(&page) {
+            links.push((c[1].to_owned(), c[2].to_owned(), c[3].to_owned()));
+        }
+        html += &page;
+    }
+
+    for (page, anchor, _) in &links {
+        assert_eq!(anchors.get(anchor), Some(page), "No {page}#{anchor}");
+    }
+    let targets: HashMap<&str, &str> = (links.iter())
+        .map(|(_, anchor, text)| (text.as_str(), anchor.as_str()))
+        .collect();
+    assert_eq!(
+        targets,
+        HashMap::from([("have", "have-1"), ("øre1", "øre-1"), ("bil", "bil")])
+    );
+    assert!(anchors.contains_key("bil-2"));
+    // Not in the dictionary
+    assert!(html.contains("自動車．→ vogn"));
+
+    let search_index = fs_err::read_to_string(dir.path().join("search.json")).unwrap();
+    assert!(search_index.contains("#bil\"") && search_index.contains("#bil-2\""));
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use std::thread;
+
+use danish_dictionary_parser::{lookup::Dictionary, serve::serve_requests};
+use serde_json::Value;
+use tiny_http::Server;
+
+fn get(url: &str) -> (u16, Value) {
+    let response = match ureq::get(url).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(_, response)) => response,
+        Err(e) => panic!("{url}: {e}"),
+    };
+    assert_eq!(response.header("Access-Control-Allow-Origin"), Some("*"));
+    (response.status(), response.into_json().unwrap())
+}
+
+/// Stops the server when the test ends, also when an assertion fails.
+struct Unblock<'a>(&'a Server);
+
+impl Drop for Unblock<'_> {
+    fn drop(&mut self) {
+        self.0.unblock();
+    }
+}
+
+#[test]
+fn answers_lookups_over_http() {
+    let dictionary = Dictionary::new(common::fixture_entries());
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", server.server_addr());
+
+    thread::scope(|s| {
+        s.spawn(|| serve_requests(&dictionary, &server));
+        let _unblock = Unblock(&server);
+
+        let (status, body) = get(&format!("{base}/headword?q=knuse"));
+        assert_eq!(status, 200);
+        assert_eq!(body[0]["entry"]["word"], "knuse");
+        assert_eq!(body.as_array().unwrap().len(), 1);
+
+        let (status, body) = get(&format!("{base}/form?q=knuste"));
+        assert_eq!(status, 200);
+        assert_eq!(body[0]["entry"]["word"], "knuse");
+        assert_eq!(body[0]["headword"], false);
+        assert_eq!(body[0]["slot"], "past");
+
+        // Percent-encoded query
+        let (_, body) = get(&format!("{base}/form?q=%C3%B8ret"));
+        assert_eq!(body[0]["entry"]["word"], "øre");
+
+        let (_, body) = get(&format!("{base}/prefix?q=bi&limit=1"));
+        assert_eq!(body.as_array().unwrap().len(), 1);
+
+        let (status, body) = get(&format!("{base}/headword"));
+        assert_eq!(status, 400);
+        assert_eq!(body["error"], "Missing query parameter q");
+        let (status, _) = get(&format!("{base}/nothing?q=knuse"));
+        assert_eq!(status, 404);
+    });
+}